@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::borrow::Cow;
+
+/// The `find(needs_escaping)` + char loop `escape` used before `escape_into`.
+fn escape_chars<'a, S: Into<Cow<'a, str>>>(input: S) -> Cow<'a, str> {
+    let input = input.into();
+    fn needs_escaping(c: char) -> bool {
+        c == '<' || c == '>' || c == '&' || c == '"' || c == '\''
+    }
+
+    if let Some(first) = input.find(needs_escaping) {
+        let mut output = String::from(&input[0..first]);
+        output.reserve(input.len() - first);
+        let rest = input[first..].chars();
+        for c in rest {
+            match c {
+                '<' => output.push_str("&lt;"),
+                '>' => output.push_str("&gt;"),
+                '&' => output.push_str("&amp;"),
+                '"' => output.push_str("&quot;"),
+                '\'' => output.push_str("&#39;"),
+                _ => output.push(c),
+            }
+        }
+        Cow::Owned(output)
+    } else {
+        input
+    }
+}
+
+fn inputs() -> [(&'static str, String); 3] {
+    [
+        ("clean", "shtml the s is silent ".repeat(200)),
+        ("sparse", "a paragraph of text with one <em> tag ".repeat(100)),
+        ("dense", "<script>alert(\"pwned\")</script>&'".repeat(100)),
+    ]
+}
+
+fn escape(c: &mut Criterion) {
+    let mut group = c.benchmark_group("escape");
+    for (name, input) in inputs() {
+        group.bench_with_input(BenchmarkId::new("chars", name), &input, |b, input| {
+            let mut buffer = String::with_capacity(input.len() * 6);
+            b.iter(|| {
+                buffer.clear();
+                buffer.push_str(&escape_chars(black_box(input.as_str())));
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("escape_into", name), &input, |b, input| {
+            let mut buffer = String::with_capacity(input.len() * 6);
+            b.iter(|| {
+                buffer.clear();
+                shtml::escape_into(black_box(input), &mut buffer).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, escape);
+criterion_main!(benches);
@@ -0,0 +1,275 @@
+#![allow(non_snake_case)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shtml::{escape_into, html, Attrs, Component, Elements, Render};
+use std::fmt::{self, Write};
+
+/// maud and markup style: push strings straight into one buffer.
+mod push_str {
+    use super::*;
+
+    pub fn table(rows: &[Vec<usize>]) -> String {
+        let mut buffer = String::new();
+        buffer.push_str("<table>");
+        for row in rows {
+            buffer.push_str("<tr>");
+            for col in row {
+                buffer.push_str("<td>");
+                buffer.push_str(itoa::Buffer::new().format(*col));
+                buffer.push_str("</td>");
+            }
+            buffer.push_str("</tr>");
+        }
+        buffer.push_str("</table>");
+        buffer
+    }
+
+    pub fn nested(depth: usize, buffer: &mut String) {
+        if depth == 0 {
+            buffer.push_str("<span>leaf</span>");
+            return;
+        }
+        buffer.push_str(r#"<div class="level">"#);
+        nested(depth - 1, buffer);
+        buffer.push_str("</div>");
+    }
+
+    pub fn escaping(items: &[String]) -> String {
+        let mut buffer = String::new();
+        buffer.push_str("<ul>");
+        for item in items {
+            buffer.push_str("<li>");
+            escape_into(item, &mut buffer).unwrap();
+            buffer.push_str("</li>");
+        }
+        buffer.push_str("</ul>");
+        buffer
+    }
+
+    pub fn spread(items: &[[(&str, String); 4]]) -> String {
+        let mut buffer = String::new();
+        for attrs in items {
+            buffer.push_str("<a");
+            for (key, value) in attrs {
+                buffer.push(' ');
+                buffer.push_str(key);
+                buffer.push_str("=\"");
+                escape_into(value, &mut buffer).unwrap();
+                buffer.push('"');
+            }
+            buffer.push_str(">link</a>");
+        }
+        buffer
+    }
+}
+
+/// askama style: `write!` through `fmt::Write` with an escaping `Display` wrapper.
+mod fmt_write {
+    use super::*;
+
+    struct Escaped<'a>(&'a str);
+
+    impl fmt::Display for Escaped<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            escape_into(self.0, f)
+        }
+    }
+
+    pub fn table(rows: &[Vec<usize>]) -> String {
+        let mut buffer = String::new();
+        write!(buffer, "<table>").unwrap();
+        for row in rows {
+            write!(buffer, "<tr>").unwrap();
+            for col in row {
+                write!(buffer, "<td>{}</td>", col).unwrap();
+            }
+            write!(buffer, "</tr>").unwrap();
+        }
+        write!(buffer, "</table>").unwrap();
+        buffer
+    }
+
+    pub fn nested(depth: usize, buffer: &mut String) {
+        if depth == 0 {
+            write!(buffer, "<span>leaf</span>").unwrap();
+            return;
+        }
+        write!(buffer, r#"<div class="level">"#).unwrap();
+        nested(depth - 1, buffer);
+        write!(buffer, "</div>").unwrap();
+    }
+
+    pub fn escaping(items: &[String]) -> String {
+        let mut buffer = String::new();
+        write!(buffer, "<ul>").unwrap();
+        for item in items {
+            write!(buffer, "<li>{}</li>", Escaped(item)).unwrap();
+        }
+        write!(buffer, "</ul>").unwrap();
+        buffer
+    }
+
+    pub fn spread(items: &[[(&str, String); 4]]) -> String {
+        let mut buffer = String::new();
+        for attrs in items {
+            write!(buffer, "<a").unwrap();
+            for (key, value) in attrs {
+                write!(buffer, r#" {}="{}""#, key, Escaped(value)).unwrap();
+            }
+            write!(buffer, ">link</a>").unwrap();
+        }
+        buffer
+    }
+}
+
+mod shtml_html {
+    use super::*;
+
+    pub fn table(rows: &[Vec<usize>]) -> Component {
+        html! {
+            <table>
+                {rows
+                    .iter()
+                    .map(|cols| {
+                        html! {
+                            <tr>
+                                {cols
+                                    .iter()
+                                    .map(|col| html! { <td>{col}</td> })
+                                    .collect::<Vec<_>>()}
+                            </tr>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </table>
+        }
+    }
+
+    fn Level(elements: Elements) -> Component {
+        html! { <div class="level">{elements}</div> }
+    }
+
+    pub fn nested(depth: usize) -> Component {
+        match depth {
+            0 => html! { <span>leaf</span> },
+            _ => html! { <Level>{nested(depth - 1)}</Level> },
+        }
+    }
+
+    pub fn escaping(items: &[String]) -> Component {
+        html! {
+            <ul>
+                {items.iter().map(|item| html! { <li>{item}</li> }).collect::<Vec<_>>()}
+            </ul>
+        }
+    }
+
+    pub fn spread(items: &[[(&str, String); 4]]) -> Component {
+        html! {
+            {items
+                .iter()
+                .map(|attrs| html! { <a {..attrs}>link</a> })
+                .collect::<Vec<_>>()}
+        }
+    }
+}
+
+fn rows() -> Vec<Vec<usize>> {
+    (0..100).map(|_| (0..10).collect()).collect()
+}
+
+fn escaping_items() -> Vec<String> {
+    (0..100)
+        .map(|i| format!("<script>alert(\"{i}\")</script> & 'friends'"))
+        .collect()
+}
+
+fn spread_items() -> Vec<[(&'static str, String); 4]> {
+    (0..100)
+        .map(|i| {
+            [
+                ("href", format!("/items/{i}?sort=asc&page=2")),
+                ("class", "link link-primary".to_string()),
+                ("data-id", i.to_string()),
+                ("title", format!("Item \"{i}\"")),
+            ]
+        })
+        .collect()
+}
+
+fn table(c: &mut Criterion) {
+    let rows = rows();
+    let mut group = c.benchmark_group("table");
+    group.bench_function("shtml", |b| {
+        b.iter(|| shtml_html::table(black_box(&rows)).html)
+    });
+    group.bench_function("push_str", |b| b.iter(|| push_str::table(black_box(&rows))));
+    group.bench_function("fmt_write", |b| {
+        b.iter(|| fmt_write::table(black_box(&rows)))
+    });
+    group.finish();
+}
+
+fn nested(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nested");
+    group.bench_function("shtml", |b| {
+        b.iter(|| shtml_html::nested(black_box(50)).html)
+    });
+    group.bench_function("push_str", |b| {
+        b.iter(|| {
+            let mut buffer = String::new();
+            push_str::nested(black_box(50), &mut buffer);
+            buffer
+        })
+    });
+    group.bench_function("fmt_write", |b| {
+        b.iter(|| {
+            let mut buffer = String::new();
+            fmt_write::nested(black_box(50), &mut buffer);
+            buffer
+        })
+    });
+    group.finish();
+}
+
+fn escaping(c: &mut Criterion) {
+    let items = escaping_items();
+    let mut group = c.benchmark_group("escaping");
+    group.bench_function("shtml", |b| {
+        b.iter(|| shtml_html::escaping(black_box(&items)).html)
+    });
+    group.bench_function("push_str", |b| {
+        b.iter(|| push_str::escaping(black_box(&items)))
+    });
+    group.bench_function("fmt_write", |b| {
+        b.iter(|| fmt_write::escaping(black_box(&items)))
+    });
+    group.finish();
+}
+
+fn spread(c: &mut Criterion) {
+    let items = spread_items();
+    let mut group = c.benchmark_group("spread");
+    group.bench_function("shtml", |b| {
+        b.iter(|| shtml_html::spread(black_box(&items)).html)
+    });
+    group.bench_function("shtml_attrs", |b| {
+        b.iter(|| {
+            let mut buffer = String::new();
+            for attrs in black_box(&items) {
+                Attrs::new().spread(attrs).render_to_string(&mut buffer);
+            }
+            buffer
+        })
+    });
+    group.bench_function("push_str", |b| {
+        b.iter(|| push_str::spread(black_box(&items)))
+    });
+    group.bench_function("fmt_write", |b| {
+        b.iter(|| fmt_write::spread(black_box(&items)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, table, nested, escaping, spread);
+criterion_main!(benches);
@@ -0,0 +1,105 @@
+use crate::escape;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, LitStr, Path, Result};
+
+/// Implements `Render` by calling `#[render(template = path)]` with `&self`,
+/// or for enums without a template, by writing the variant's name or its
+/// `#[render(text = "...")]`.
+pub fn render_macro(input: DeriveInput) -> Result<TokenStream2> {
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        attrs,
+        ..
+    } = input;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut render_nodes = None;
+    let (render, size_hint) = match (render_attr(&attrs)?, data) {
+        (RenderAttr::Template(template), _) => {
+            let render = quote!(::shtml::Render::render_to_string(&#template(self), buffer));
+            render_nodes = Some(quote! {
+                fn render_nodes(&self, nodes: &mut ::shtml::__private::Vec<::shtml::Node>) {
+                    ::shtml::Render::render_nodes(&#template(self), nodes)
+                }
+            });
+            (render, quote!(0))
+        }
+        (RenderAttr::Text(text), _) => {
+            return Err(Error::new(
+                text.span(),
+                "`text` goes on enum variants, use `template` here",
+            ))
+        }
+        (RenderAttr::None, Data::Enum(data)) => {
+            let texts = data
+                .variants
+                .iter()
+                .map(|variant| match render_attr(&variant.attrs)? {
+                    RenderAttr::Text(text) => Ok(escape(&text.value())),
+                    RenderAttr::None => Ok(escape(&variant.ident.to_string())),
+                    RenderAttr::Template(template) => Err(Error::new_spanned(
+                        template,
+                        "`template` goes on the enum, use `text` here",
+                    )),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let variants = data.variants.iter().map(|variant| &variant.ident);
+            let arms = variants
+                .zip(&texts)
+                .map(|(variant, text)| quote!(Self::#variant { .. } => #text))
+                .collect::<Vec<_>>();
+            (
+                quote!(buffer.push_str(match self { #(#arms,)* })),
+                quote!(match self { #(#arms.len(),)* }),
+            )
+        }
+        (RenderAttr::None, _) => {
+            return Err(Error::new(
+                ident.span(),
+                "add #[render(template = path)] with a fn(&Self) -> Component to render this",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::shtml::Render for #ident #ty_generics #where_clause {
+            fn render_to_string(&self, buffer: &mut ::shtml::__private::String) {
+                #render
+            }
+
+            fn size_hint(&self) -> usize {
+                #size_hint
+            }
+
+            #render_nodes
+        }
+    })
+}
+
+enum RenderAttr {
+    None,
+    Text(LitStr),
+    Template(Path),
+}
+
+fn render_attr(attrs: &[Attribute]) -> Result<RenderAttr> {
+    let mut render = RenderAttr::None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("render")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("text") {
+                render = RenderAttr::Text(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("template") {
+                render = RenderAttr::Template(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `text` or `template`"))
+            }
+        })?;
+    }
+
+    Ok(render)
+}
@@ -0,0 +1,114 @@
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::{
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    rc::Rc,
+};
+
+/// The static chunks of one `html!` call, split into a part for the call
+/// itself and one for every component's children in it.
+///
+/// Static strings are pushed from the chunks instead of literals and the
+/// chunks are written to a manifest when the macro expands. Rebuilding
+/// expands the macro again, so after `cargo check` a running debug build
+/// picks up changed markup as long as the code around it, hashed into the
+/// manifest, stayed the same.
+#[derive(Debug, Clone)]
+pub struct HotReload {
+    parts: Rc<RefCell<Vec<Vec<String>>>>,
+    pub part: usize,
+}
+
+impl HotReload {
+    /// Only with the feature in debug builds, release builds get plain
+    /// literals with no chunk lookups or manifests.
+    pub fn new() -> Option<Self> {
+        (cfg!(feature = "hot-reload") && cfg!(debug_assertions)).then(|| Self {
+            parts: Rc::new(RefCell::new(vec![vec![]])),
+            part: 0,
+        })
+    }
+
+    pub fn child(&self) -> Self {
+        let mut parts = self.parts.borrow_mut();
+        parts.push(vec![]);
+        Self {
+            parts: self.parts.clone(),
+            part: parts.len() - 1,
+        }
+    }
+
+    /// Records a chunk and returns its index in this part.
+    pub fn push(&self, chunk: &str) -> usize {
+        let mut parts = self.parts.borrow_mut();
+        parts[self.part].push(chunk.to_owned());
+        parts[self.part].len() - 1
+    }
+
+    /// Writes the manifest for the call at `site` and declares the chunks
+    /// `component` reads from.
+    pub fn wrap(&self, site: &str, component: TokenStream2) -> TokenStream2 {
+        let parts = self.parts.borrow();
+        let mut hasher = DefaultHasher::new();
+        component.to_string().hash(&mut hasher);
+        let structure = hasher.finish();
+
+        let manifest = manifest_path(site);
+        write_manifest(&manifest, structure, &parts);
+        let manifest = manifest.to_string_lossy();
+        let structure = Literal::u64_suffixed(structure);
+        let parts = parts.iter().map(|chunks| quote!(&[#(#chunks),*]));
+
+        quote! {
+            {
+                let __shtml_chunks =
+                    ::shtml::hot_reload::chunks(#manifest, #structure, &[#(#parts),*]);
+                #component
+            }
+        }
+    }
+}
+
+/// Identifies the macro call by where it is, which stays the same while only
+/// markup on the lines of the call changes.
+pub fn call_site() -> String {
+    let span = Span::call_site();
+    let start = span.start();
+    format!(
+        "{}/{}:{}:{}",
+        env::var("CARGO_MANIFEST_DIR").unwrap_or_default(),
+        span.file(),
+        start.line,
+        start.column
+    )
+}
+
+fn manifest_path(site: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    site.hash(&mut hasher);
+    env::temp_dir()
+        .join("shtml-hot-reload")
+        .join(format!("{:016x}", hasher.finish()))
+}
+
+/// The structure hash on the first line, then every part as its chunk count
+/// followed by each chunk's byte length and the chunk itself.
+fn write_manifest(path: &PathBuf, structure: u64, parts: &[Vec<String>]) {
+    let mut manifest = format!("{}\n", structure);
+    for chunks in parts {
+        manifest.push_str(&format!("{}\n", chunks.len()));
+        for chunk in chunks {
+            manifest.push_str(&format!("{}\n{}", chunk.len(), chunk));
+        }
+    }
+    if fs::read_to_string(path).ok().as_deref() != Some(manifest.as_str()) {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, manifest);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::escape;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Error, Expr, Ident, LitStr, Result, Token,
+};
+
+const PLURAL_FORMS: [&str; 3] = ["zero", "one", "other"];
+
+/// `t!("key", name = value, ...)`
+pub struct Translate {
+    key: LitStr,
+    args: Vec<(Ident, Expr)>,
+}
+
+impl Parse for Translate {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key = input.parse()?;
+        let mut args = vec![];
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            let pairs =
+                Punctuated::<(Ident, Expr), Token![,]>::parse_terminated_with(input, |input| {
+                    let name = input.parse()?;
+                    input.parse::<Token![=]>()?;
+                    Ok((name, input.parse()?))
+                })?;
+            args = pairs.into_iter().collect();
+        }
+
+        Ok(Self { key, args })
+    }
+}
+
+/// A message split into text and `{name}` placeholders.
+#[derive(Debug)]
+enum Segment {
+    Text(String),
+    Arg(String),
+}
+
+/// One locale's message for a key, with its plural forms if it has any.
+#[derive(Debug)]
+enum Message {
+    Single(Vec<Segment>),
+    Plural(BTreeMap<String, Vec<Segment>>),
+}
+
+/// Looks `key` up in every catalog under `SHTML_LOCALES` (`locales` by
+/// default) and renders the message for the locale in context, falling back
+/// to the default locale, `SHTML_DEFAULT_LOCALE` or `en`, which must have it.
+pub fn t_macro(input: Translate) -> Result<TokenStream2> {
+    let Translate { key, args } = input;
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let dir = root.join(env::var("SHTML_LOCALES").unwrap_or_else(|_| "locales".to_owned()));
+    let default = env::var("SHTML_DEFAULT_LOCALE").unwrap_or_else(|_| "en".to_owned());
+    let catalogs = catalogs(&dir).map_err(|message| Error::new(key.span(), message))?;
+    let missing = || {
+        Error::new(
+            key.span(),
+            format!(
+                "missing key `{}` in {}",
+                key.value(),
+                dir.join(format!("{}.txt", default)).display()
+            ),
+        )
+    };
+
+    let default_message = catalogs
+        .iter()
+        .find(|(locale, ..)| *locale == default)
+        .and_then(|(_, _, messages)| messages.get(&key.value()))
+        .ok_or_else(missing)?;
+    let mut locales = vec![(default.clone(), default_message)];
+    for (locale, _, messages) in &catalogs {
+        if let (false, Some(message)) = (*locale == default, messages.get(&key.value())) {
+            locales.push((locale.clone(), message));
+        }
+    }
+
+    let bindings = args
+        .iter()
+        .map(|(name, _)| format_ident!("__shtml_arg_{}", name))
+        .collect::<Vec<_>>();
+    let exprs = args.iter().map(|(_, expr)| expr);
+    let has_count = args.iter().any(|(name, _)| name == "count");
+    let arms = locales
+        .iter()
+        .map(|(locale, message)| {
+            let body = render_message(&key, locale, message, &args, has_count)?;
+            Ok((locale.as_str(), body))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let names = arms.iter().map(|(locale, _)| *locale);
+    let indexes = (0..arms.len()).skip(1);
+    let bodies = arms.iter().map(|(_, body)| body).collect::<Vec<_>>();
+    let default_body = bodies[0];
+    let other_bodies = &bodies[1..];
+    let files = catalogs
+        .iter()
+        .map(|(_, path, _)| path.to_string_lossy().into_owned());
+    let plural = match has_count {
+        true => quote!(let __shtml_plural = *__shtml_arg_count as i64;),
+        false => quote!(),
+    };
+
+    Ok(quote! {
+        {
+            #(const _: &str = include_str!(#files);)*
+            // bound by a `match` so temporaries in the arguments live until
+            // they're rendered
+            match (#(&(#exprs),)*) {
+                (#(#bindings,)*) => {
+                    #plural
+                    let mut __shtml_buf = ::shtml::__private::String::new();
+                    match ::shtml::i18n::select(&[#(#names),*]) {
+                        #(#indexes => { #other_bodies })*
+                        _ => { #default_body }
+                    }
+                    ::shtml::Component { html: __shtml_buf }
+                }
+            }
+        }
+    })
+}
+
+fn render_message(
+    key: &LitStr,
+    locale: &str,
+    message: &Message,
+    args: &[(Ident, Expr)],
+    has_count: bool,
+) -> Result<TokenStream2> {
+    match message {
+        Message::Single(segments) => render_segments(key, segments, args),
+        Message::Plural(_) if !has_count => Err(Error::new(
+            key.span(),
+            format!("`{}` has plural forms, pass `count = ...`", key.value()),
+        )),
+        Message::Plural(forms) => {
+            let other = forms.get("other").ok_or_else(|| {
+                Error::new(
+                    key.span(),
+                    format!("`{}.other` is missing for {}", key.value(), locale),
+                )
+            })?;
+            let other = render_segments(key, other, args)?;
+            let one = match forms.get("one") {
+                Some(one) => render_segments(key, one, args)?,
+                None => other.clone(),
+            };
+            let zero = match forms.get("zero") {
+                Some(zero) => {
+                    let zero = render_segments(key, zero, args)?;
+                    quote!(if __shtml_plural == 0 { #zero } else)
+                }
+                None => quote!(),
+            };
+
+            Ok(quote! {
+                #zero if ::shtml::i18n::is_one(#locale, __shtml_plural) { #one } else { #other }
+            })
+        }
+    }
+}
+
+fn render_segments(
+    key: &LitStr,
+    segments: &[Segment],
+    args: &[(Ident, Expr)],
+) -> Result<TokenStream2> {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => {
+                let text = escape(text);
+                Ok(quote!(__shtml_buf.push_str(#text);))
+            }
+            Segment::Arg(name) => match args.iter().any(|(arg, _)| arg == name) {
+                true => {
+                    let binding = format_ident!("__shtml_arg_{}", name);
+                    Ok(quote!(::shtml::Render::render_to_string(#binding, &mut __shtml_buf);))
+                }
+                false => Err(Error::new(
+                    key.span(),
+                    format!("`{}` uses {{{}}}, pass `{} = ...`", key.value(), name, name),
+                )),
+            },
+        })
+        .collect()
+}
+
+type Catalog = (String, PathBuf, BTreeMap<String, Message>);
+
+/// Every `<locale>.txt` in `dir`, sorted by locale.
+fn catalogs(dir: &Path) -> std::result::Result<Vec<Catalog>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("couldn't read {}: {}", dir.display(), e))?;
+    let mut catalogs = vec![];
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        let locale = match (path.extension(), path.file_stem()) {
+            (Some(extension), Some(stem)) if extension == "txt" => {
+                stem.to_string_lossy().into_owned()
+            }
+            _ => continue,
+        };
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        let messages = parse_catalog(&source)
+            .map_err(|(line, message)| format!("{}:{}: {}", path.display(), line, message))?;
+        catalogs.push((locale, path, messages));
+    }
+    catalogs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(catalogs)
+}
+
+/// Parses `key = message` lines, skipping blank lines and `#` comments. Keys
+/// ending in `.zero`, `.one` or `.other` are the plural forms of the key
+/// before them.
+fn parse_catalog(source: &str) -> std::result::Result<BTreeMap<String, Message>, (usize, String)> {
+    let mut messages = BTreeMap::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| (i + 1, "expected `key = message`".to_owned()))?;
+        let (key, value) = (key.trim(), segments(value.trim()));
+        let plural = key
+            .rsplit_once('.')
+            .filter(|(_, form)| PLURAL_FORMS.contains(form));
+        match plural {
+            Some((key, form)) => {
+                let message = messages
+                    .entry(key.to_owned())
+                    .or_insert_with(|| Message::Plural(BTreeMap::new()));
+                match message {
+                    Message::Plural(forms) => {
+                        forms.insert(form.to_owned(), value);
+                    }
+                    Message::Single(_) => {
+                        return Err((i + 1, format!("`{}` has a message and plural forms", key)))
+                    }
+                }
+            }
+            None => {
+                messages.insert(key.to_owned(), Message::Single(value));
+            }
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Splits a message on `{name}` placeholders, with `{{` and `}}` for braces.
+fn segments(message: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut text = String::new();
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(c);
+            }
+            ('{', _) => {
+                let name = chars.by_ref().take_while(|c| *c != '}').collect::<String>();
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Arg(name.trim().to_owned()));
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    segments
+}
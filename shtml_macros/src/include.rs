@@ -0,0 +1,206 @@
+use crate::{hot_reload, html_component};
+use proc_macro2::{
+    fallback, Delimiter, LineColumn, Spacing, Span, TokenStream as TokenStream2, TokenTree,
+};
+use quote::quote;
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+use syn::{Error, LitStr, Result};
+
+/// Reads a template relative to the crate root and renders it like `html!`.
+///
+/// The file is tokenized with proc-macro2's fallback implementation so spans
+/// point into the file instead of the macro call, which the whitespace rules
+/// and error locations rely on. The generated code is written to a `.rs` file
+/// named after the template, with every token from the template on the line
+/// it came from, and `include!`d, so the compiler's errors about the
+/// template's expressions and attributes point at the right lines. An
+/// `include_str!` of the template makes cargo rebuild when it changes.
+pub fn include_html_macro(path: LitStr) -> Result<TokenStream2> {
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = Path::new(&root).join(path.value());
+    let source = fs::read_to_string(&file).map_err(|e| {
+        Error::new(
+            path.span(),
+            format!("couldn't read {}: {}", file.display(), e),
+        )
+    })?;
+
+    let site = hot_reload::call_site();
+    let code = render_file(&source, &site).map_err(|(location, message)| {
+        Error::new(
+            path.span(),
+            format!(
+                "{}:{}:{}: {}",
+                path.value(),
+                location.line,
+                location.column + 1,
+                message
+            ),
+        )
+    })?;
+    let generated = generated_path(&site, &file);
+    write_generated(&generated, &code).map_err(|e| {
+        Error::new(
+            path.span(),
+            format!("couldn't write {}: {}", generated.display(), e),
+        )
+    })?;
+    let file = file.to_string_lossy();
+    let generated = generated.to_string_lossy();
+
+    Ok(quote! {
+        {
+            const _: &str = include_str!(#file);
+            // the included code has real spans, so clippy would lint it as
+            // if the user had written it
+            #[allow(clippy::all)]
+            let component = include!(#generated);
+            component
+        }
+    })
+}
+
+struct Fallback;
+
+impl Drop for Fallback {
+    fn drop(&mut self) {
+        fallback::unforce();
+    }
+}
+
+fn render_file(source: &str, site: &str) -> std::result::Result<String, (LineColumn, String)> {
+    // `force` flips a process wide switch so every token proc-macro2 makes
+    // until `unforce` is a fallback token. That's safe because rustc expands
+    // macros one at a time on a single thread, so no other expansion runs
+    // while it's on, none of the tokens made here outlive this function
+    // since the output is printed to a string, and the guard turns it off
+    // again even if rendering panics.
+    fallback::force();
+    let _fallback = Fallback;
+    let tokens = source.parse::<TokenStream2>().map_err(|e| {
+        let message = "couldn't tokenize the template, look for unbalanced brackets or quotes";
+        (e.span().start(), message.to_string())
+    })?;
+    let component =
+        html_component(tokens, site, None, None).map_err(|e| (e.span().start(), e.to_string()))?;
+
+    let mut layout = Layout {
+        code: String::new(),
+        line: 1,
+        column: 0,
+        joint: false,
+        placed: false,
+    };
+    layout.tokens(component);
+
+    Ok(layout.code)
+}
+
+/// Prints tokens as code, putting the ones from the template on their line,
+/// and at their column when the line isn't already past it. Generated tokens
+/// have no place in the file and just follow the previous one.
+struct Layout {
+    code: String,
+    line: usize,
+    column: usize,
+    /// The previous token was a punct joined to the next one, like the first
+    /// `:` of `::`.
+    joint: bool,
+    /// The previous token came from the template and is where it was in the
+    /// file, so a token starting right after it was next to it there too.
+    placed: bool,
+}
+
+impl Layout {
+    fn tokens(&mut self, tokens: TokenStream2) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.token(open, group.span_open());
+                    self.tokens(group.stream());
+                    self.token(close, group.span_close());
+                }
+                TokenTree::Punct(punct) => {
+                    self.token(&punct.as_char().to_string(), punct.span());
+                    self.joint = punct.spacing() == Spacing::Joint;
+                }
+                TokenTree::Ident(ident) => self.token(&ident.to_string(), ident.span()),
+                TokenTree::Literal(literal) => self.token(&literal.to_string(), literal.span()),
+            }
+        }
+    }
+
+    fn token(&mut self, text: &str, span: Span) {
+        if text.is_empty() {
+            return;
+        }
+        let joint = std::mem::take(&mut self.joint);
+        let placed = std::mem::take(&mut self.placed);
+        // generated tokens have the empty call site span
+        let start = Some(span.start()).filter(|_| !span.byte_range().is_empty());
+        if let Some(start) = start.filter(|start| start.line > self.line) {
+            self.code.extend((self.line..start.line).map(|_| '\n'));
+            (self.line, self.column) = (start.line, 0);
+        }
+        match start.filter(|start| start.column > self.column) {
+            Some(start) => {
+                self.code.extend((self.column..start.column).map(|_| ' '));
+                self.column = start.column;
+            }
+            None if placed && start.is_some_and(|start| start.column == self.column) => {}
+            None if !joint && self.column > 0 => {
+                self.code.push(' ');
+                self.column += 1;
+            }
+            None => {}
+        }
+
+        self.placed =
+            start.is_some_and(|start| (start.line, start.column) == (self.line, self.column));
+        self.code.push_str(text);
+        match text.rsplit_once('\n') {
+            Some((before, after)) => {
+                self.line += before.matches('\n').count() + 1;
+                self.column = after.chars().count();
+            }
+            None => self.column += text.chars().count(),
+        }
+    }
+}
+
+/// Where the generated code for the template `file` included at `site` goes,
+/// next to the hot reload manifests in the temp directory.
+fn generated_path(site: &str, file: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    site.hash(&mut hasher);
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    env::temp_dir()
+        .join("shtml-include")
+        .join(format!("{:016x}", hasher.finish()))
+        .join(format!("{}.rs", name))
+}
+
+/// Writes `code` unless it's there already, through a temporary file so a
+/// build expanding the same template at the same time never reads half of it.
+fn write_generated(path: &Path, code: &str) -> std::io::Result<()> {
+    if fs::read_to_string(path).ok().as_deref() == Some(code) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temporary = path.with_extension(format!("rs.{}", std::process::id()));
+    fs::write(&temporary, code)?;
+    fs::rename(&temporary, path)
+}
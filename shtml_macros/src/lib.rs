@@ -1,11 +1,27 @@
 mod chaos;
+mod derive;
+mod hot_reload;
+#[cfg(feature = "i18n")]
+mod i18n;
+mod include;
+mod nodes;
+mod whitespace;
+mod xml;
 
 use proc_macro::TokenStream;
-use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro2::{LineColumn, Span, TokenStream as TokenStream2};
 use quote::{quote, ToTokens};
-use rstml::{self, node::Node, Parser, ParserConfig};
+use rstml::{
+    self,
+    node::{KeyedAttribute, Node, NodeAttribute, NodeBlock, NodeElement},
+    Parser, ParserConfig,
+};
 use std::{collections::HashSet, fmt::Debug};
-use syn::{parse_macro_input, Ident, ItemFn, LitStr, Result};
+use syn::{
+    parse::ParseStream, parse_macro_input, DeriveInput, Ident, ItemFn, LitStr, Result, Token,
+};
+use hot_reload::HotReload;
+use whitespace::{node_range, span_range, Whitespace};
 
 #[proc_macro]
 pub fn html(input: TokenStream) -> TokenStream {
@@ -16,35 +32,148 @@ pub fn html(input: TokenStream) -> TokenStream {
 }
 
 fn html_macro(input: TokenStream) -> Result<TokenStream2> {
-    let size_hint = input.to_string().len();
-    let config = ParserConfig::new()
+    html_component(input.into(), &hot_reload::call_site(), None, None)
+}
+
+#[proc_macro]
+pub fn html_into(input: TokenStream) -> TokenStream {
+    match html_into_macro(input) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// `html_into!(buffer, template)`, rendering straight into a `&mut String`.
+fn html_into_macro(input: TokenStream) -> Result<TokenStream2> {
+    let (buffer, template) = syn::parse::Parser::parse(
+        |input: ParseStream| {
+            let buffer = input.parse::<syn::Expr>()?;
+            input.parse::<Token![,]>()?;
+            Ok((buffer, input.parse::<TokenStream2>()?))
+        },
+        input,
+    )?;
+    html_component(template, &hot_reload::call_site(), None, Some(&buffer))
+}
+
+/// Parses a template and builds the `Component` expression for it. `site`
+/// identifies the macro call for hot reloading. `xml` is the prolog for
+/// `xml!`, which also turns off html's void elements and components. With
+/// `into`, the template is appended to that buffer instead.
+fn html_component(
+    input: TokenStream2,
+    site: &str,
+    xml: Option<&str>,
+    into: Option<&syn::Expr>,
+) -> Result<TokenStream2> {
+    let nodes = parser(xml.is_some()).parse_simple(input)?;
+    let buf = Ident::new("__shtml_buf", Span::call_site());
+    let hot_reload = HotReload::new();
+    let mut output = Output::new(buf, hot_reload.clone());
+    if let Some(prolog) = xml {
+        output.xml = true;
+        output.foreign = true;
+        output.push_str(prolog);
+    }
+    render_nodes(&mut output, &nodes, None, None);
+
+    let component = match into {
+        Some(buffer) => output.render_into(buffer),
+        None => output.into_component(),
+    };
+    Ok(match hot_reload {
+        Some(hot_reload) => hot_reload.wrap(site, component),
+        None => component,
+    })
+}
+
+/// The template parser, where html's void elements never have children unless
+/// it's for `xml!`.
+fn parser(xml: bool) -> Parser {
+    let mut config = ParserConfig::new()
         .recover_block(true)
-        .always_self_closed_elements(HashSet::from([
+        .transform_block(fmt_block);
+    if !xml {
+        config = config.always_self_closed_elements(HashSet::from([
             "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
             "track", "wbr",
         ]));
-    let parser = Parser::new(config);
+    }
+    Parser::new(config)
+}
 
-    let nodes = parser.parse_simple(input)?;
-    let buf = Ident::new("__shtml_buf", Span::call_site());
-    let mut output = Output {
-        buf: buf.clone(),
-        static_string: String::new(),
-        tokens: vec![],
-    };
-    nodes
-        .into_iter()
-        .for_each(|node| render(&mut output, &node));
+/// Turns `{%expr}` into `{::shtml::Fmt(&(expr))}`.
+fn fmt_block(input: ParseStream) -> Result<Option<TokenStream2>> {
+    if !input.peek(Token![%]) {
+        return Ok(None);
+    }
+    input.parse::<Token![%]>()?;
+    let expr = input.parse::<syn::Expr>()?;
+    Ok(Some(quote!(::shtml::Fmt(&(#expr)))))
+}
 
-    let tokens = output.to_token_stream();
+#[proc_macro]
+pub fn xml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as xml::Prolog);
+    match xml::xml_macro(input) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
 
-    Ok(quote! {
-        {
-            let mut #buf = String::with_capacity(#size_hint);
-            #tokens
-            Component { html: #buf }
+#[proc_macro]
+pub fn nodes(input: TokenStream) -> TokenStream {
+    match nodes::nodes_macro(input.into()) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[proc_macro]
+pub fn include_html(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    match include::include_html_macro(path) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Renders sibling nodes along with the whitespace between them. `open` and
+/// `close` are where the parent's tags end and start.
+fn render_nodes(
+    output: &mut Output,
+    nodes: &[Node],
+    open: Option<LineColumn>,
+    close: Option<LineColumn>,
+) {
+    let mut end = open;
+    for (i, node) in nodes.iter().enumerate() {
+        let range = node_range(node);
+        if let (Some(end), Some((start, _))) = (end, range) {
+            let mut gap = String::new();
+            match i {
+                0 => output.whitespace.leading(&mut gap, end, start),
+                _ => output.whitespace.gap(&mut gap, end, start),
+            }
+            output.push_str(&gap);
         }
-    })
+        render(output, node);
+        end = range.map(|(_, end)| end);
+    }
+
+    if let (Some(end), Some(close), false) = (end, close, nodes.is_empty()) {
+        let mut gap = String::new();
+        output.whitespace.trailing(&mut gap, end, close);
+        output.push_str(&gap);
+    }
+}
+
+fn end_of(tokens: impl ToTokens) -> Option<LineColumn> {
+    span_range(tokens.to_token_stream()).map(|(_, end)| end)
+}
+
+fn start_of(tokens: impl ToTokens) -> Option<LineColumn> {
+    span_range(tokens.to_token_stream()).map(|(start, _)| start)
 }
 
 fn render(output: &mut Output, node: &Node) {
@@ -61,153 +190,99 @@ fn render(output: &mut Output, node: &Node) {
                 .push_str(&d.value.to_token_stream_string());
             output.push_str(">");
         }
-        Node::Fragment(n) => {
-            for node in &n.children {
-                render(output, &node)
-            }
-        }
+        Node::Fragment(n) => render_nodes(
+            output,
+            &n.children,
+            end_of(&n.tag_open),
+            n.tag_close.as_ref().and_then(start_of),
+        ),
         Node::Element(n) => {
-            let component_name = match &n.name() {
-                rstml::node::NodeName::Path(syn::ExprPath { path, .. }) => match path.get_ident() {
-                    Some(ident) => match ident.to_string().get(0..1) {
-                        Some(first_letter) => match first_letter.to_uppercase() == first_letter {
-                            true => Some(ident),
-                            false => None,
-                        },
-                        None => None,
-                    },
-                    None => todo!(),
-                },
-                rstml::node::NodeName::Punctuated(_) => todo!(),
-                rstml::node::NodeName::Block(_) => todo!(),
-            };
+            let component_name = component_name(n).filter(|_| !output.foreign);
             match component_name {
-                Some(fn_name) => {
-                    let mut inputs = n
-                        .open_tag
-                        .attributes
-                        .iter()
-                        .map(|attr| match attr {
-                            rstml::node::NodeAttribute::Block(_) => todo!(),
-                            rstml::node::NodeAttribute::Attribute(attr) => {
-                                #[cfg(feature = "chaos")]
-                                let key = &attr.key;
-                                let value = attr.value();
-
-                                #[cfg(feature = "chaos")]
-                                quote! { #key: #value }
-
-                                #[cfg(not(feature = "chaos"))]
-                                quote! { #value }
-                            }
-                        })
-                        .collect::<Vec<_>>();
-
-                    let mut inner_output = Output::new(output.buf.clone());
-
-                    for node in &n.children {
-                        render(&mut inner_output, &node);
-                    }
-
-                    let buf = inner_output.buf.clone();
-                    let inner_tokens = inner_output.to_token_stream();
-
-                    match inner_tokens.is_empty() {
-                        false => {
-                            let inner_tokens = quote! {
-                                {
-                                    let mut #buf = String::new();
-                                    #inner_tokens
-                                    Component { html: #buf }
-                                }
-                            };
-
-                            inputs.push(inner_tokens);
+                Some(fn_name) if fn_name == "Provider" => {
+                    let value = match provider_value(fn_name, &n.open_tag.attributes) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            output.push_tokens(e.to_compile_error());
+                            return;
                         }
-                        _ => {}
-                    }
+                    };
 
-                    #[cfg(feature = "chaos")]
-                    let tokens = quote! { #fn_name { #(#inputs,)* } };
+                    let mut inner_output = output.child();
+                    render_nodes(
+                        &mut inner_output,
+                        &n.children,
+                        end_of(&n.open_tag),
+                        n.close_tag.as_ref().and_then(start_of),
+                    );
 
-                    #[cfg(not(feature = "chaos"))]
-                    let tokens = quote! { #fn_name(#(#inputs,)*) };
+                    let inner_tokens = inner_output.into_component();
 
+                    output.push_tokens(quote! {
+                        ::shtml::Provider(#value, || #inner_tokens)
+                    });
+                }
+                Some(fn_name) => {
+                    let tokens = component_call(output.child(), fn_name, n);
                     output.push_tokens(tokens);
                 }
                 None => {
+                    let name = n.open_tag.name.to_string();
+                    if !output.xml {
+                        for error in lint_attributes(&n.open_tag.attributes) {
+                            output.push_tokens(error.to_compile_error());
+                        }
+                    }
                     output.push_str("<");
-                    output.push_str(&n.open_tag.name.to_string());
-                    for attr in &n.open_tag.attributes {
-                        match attr {
-                            rstml::node::NodeAttribute::Block(block) => {
-                                match block {
-                                    rstml::node::NodeBlock::ValidBlock(valid_block) => {
-                                        for stmt in &valid_block.stmts {
-                                            match stmt {
-                                                syn::Stmt::Expr(expr_expr, _expr_semi) => {
-                                                    match expr_expr {
-                                                        syn::Expr::Range(expr_range) => {
-                                                            match &expr_range.end {
-                                                                Some(box_expr) => {
-                                                                    let tokens = (*box_expr.clone()).to_token_stream();
-
-                                                                    output.push_tokens(tokens);
-                                                                }
-                                                                _ => {}
-                                                            }
-                                                        }
-                                                        _ => {}
-                                                    }
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            },
-                            rstml::node::NodeAttribute::Attribute(attr) => {
-                                output.static_string.push(' ');
-                                output.push_str(&attr.key.to_string());
-                                match attr.value_literal_string() {
-                                    Some(s) => {
-                                        output.push_str("=\"");
-                                        output.push_str(&s);
-                                        output.push_str("\"");
-                                    }
-                                    None => match attr.value() {
-                                        Some(expr) => {
-                                            output.push_str("=\"");
-                                            let tokens = expr.to_token_stream();
-                                            output.push_tokens(tokens);
-                                            output.push_str("\"");
-                                        }
-                                        None => {
-                                            // TODO: bool attr?
-                                        }
-                                    },
-                                }
+                    output.push_str(&name);
+                    let nonce = !output.xml && needs_nonce(&name, &n.open_tag.attributes);
+                    let has_spread = n
+                        .open_tag
+                        .attributes
+                        .iter()
+                        .any(|attr| matches!(attr, NodeAttribute::Block(_)));
+                    match has_spread {
+                        true => output.push_tokens(attrs(&n.open_tag.attributes, nonce)),
+                        false => {
+                            if nonce {
+                                output.push_tokens(quote!(::shtml::csp::nonce()));
                             }
+                            render_attributes(output, &n.open_tag.attributes);
                         }
                     }
+                    let foreign = output.foreign || ["svg", "math"].contains(&name.as_str());
                     match &n.children.is_empty() {
                         true => match &n.close_tag {
-                            Some(tag) => {
+                            Some(tag) if !foreign => {
                                 output.push_str(">");
                                 output.push_str("</");
                                 output.push_str(&tag.name.to_string());
                                 output.push_str(">");
                             }
-                            None => {
+                            _ => {
                                 output.push_str("/>");
                             }
                         },
                         false => {
                             output.push_str(">");
-                            for child in &n.children {
-                                render(output, &child);
+                            let (whitespace, parent_foreign) = (output.whitespace, output.foreign);
+                            output.foreign = foreign && (output.xml || name != "foreignObject");
+                            let open = end_of(&n.open_tag);
+                            if let (Whitespace::Collapse, Some(open), true) =
+                                (whitespace, open, Whitespace::preserves(&name))
+                            {
+                                output.whitespace = Whitespace::Preserve {
+                                    indent: whitespace::indent(&n.children, open),
+                                };
                             }
+                            render_nodes(
+                                output,
+                                &n.children,
+                                open,
+                                n.close_tag.as_ref().and_then(start_of),
+                            );
+                            output.whitespace = whitespace;
+                            output.foreign = parent_foreign;
 
                             match &n.close_tag {
                                 Some(tag) => {
@@ -229,23 +304,442 @@ fn render(output: &mut Output, node: &Node) {
             output.push_tokens(tokens);
         }
         Node::Text(n) => output.push_str(&n.value_string()),
-        Node::RawText(n) => output.push_str(&n.to_token_stream_string()),
+        Node::RawText(n) => output.push_str(&whitespace::raw_text(n, output.whitespace)),
+    }
+}
+
+/// The function an element like `<Card>` calls, since components are named
+/// like types and elements aren't.
+fn component_name(n: &NodeElement) -> Option<&Ident> {
+    match &n.name() {
+        rstml::node::NodeName::Path(syn::ExprPath { path, .. }) => match path.get_ident() {
+            Some(ident) => match ident.to_string().get(0..1) {
+                Some(first_letter) => match first_letter.to_uppercase() == first_letter {
+                    true => Some(ident),
+                    false => None,
+                },
+                None => None,
+            },
+            None => todo!(),
+        },
+        rstml::node::NodeName::Punctuated(_) => None,
+        rstml::node::NodeName::Block(_) => todo!(),
+    }
+}
+
+/// Calls the component `fn_name` with the element's attributes, and its
+/// children rendered into `children` as the last argument.
+fn component_call(mut children: Output, fn_name: &Ident, n: &NodeElement) -> TokenStream2 {
+    let mut inputs = n
+        .open_tag
+        .attributes
+        .iter()
+        .map(|attr| match attr {
+            rstml::node::NodeAttribute::Block(block) => {
+                let value = spread(block);
+
+                #[cfg(feature = "chaos")]
+                quote! { attrs: ::shtml::Attrs::new().spread(#value) }
+
+                #[cfg(not(feature = "chaos"))]
+                quote! { ::shtml::Attrs::new().spread(#value) }
+            }
+            rstml::node::NodeAttribute::Attribute(attr) => {
+                #[cfg(feature = "chaos")]
+                let key = &attr.key;
+                let value = attr.value();
+
+                #[cfg(feature = "chaos")]
+                quote! { #key: #value }
+
+                #[cfg(not(feature = "chaos"))]
+                quote! { #value }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    render_nodes(
+        &mut children,
+        &n.children,
+        end_of(&n.open_tag),
+        n.close_tag.as_ref().and_then(start_of),
+    );
+
+    if !children.is_empty() {
+        inputs.push(children.into_component());
+    }
+
+    #[cfg(feature = "chaos")]
+    let tokens = quote! { #fn_name { #(#inputs,)* } };
+
+    #[cfg(not(feature = "chaos"))]
+    let tokens = quote! { #fn_name(#(#inputs,)*) };
+
+    tokens
+}
+
+/// The `value=...` of a `<Provider>`, which is the only attribute it takes.
+fn provider_value<'a>(name: &Ident, attributes: &'a [NodeAttribute]) -> Result<&'a syn::Expr> {
+    let mut value = None;
+    for attr in attributes {
+        match attr {
+            NodeAttribute::Attribute(attr) if attr.key.to_string() == "value" => {
+                value = attr.value();
+            }
+            NodeAttribute::Attribute(attr) => {
+                return Err(syn::Error::new_spanned(
+                    &attr.key,
+                    "`<Provider>` only takes `value=...`",
+                ))
+            }
+            NodeAttribute::Block(block) => {
+                return Err(syn::Error::new_spanned(
+                    block,
+                    "`<Provider>` only takes `value=...`",
+                ))
+            }
+        }
+    }
+
+    value.ok_or_else(|| {
+        syn::Error::new(
+            name.span(),
+            "`<Provider>` needs the value to provide, like `<Provider value=Theme(\"dark\")>`",
+        )
+    })
+}
+
+/// Whether `html!` adds the request's CSP nonce to this element, which it does
+/// for `<script>` and `<style>` unless they set `nonce` themselves. A `nonce`
+/// in a spread can't be seen here, so it replaces the one `attrs` adds.
+fn needs_nonce(name: &str, attributes: &[NodeAttribute]) -> bool {
+    let has_nonce = attributes.iter().any(|attr| match attr {
+        NodeAttribute::Attribute(attr) => attr.key.to_string().eq_ignore_ascii_case("nonce"),
+        NodeAttribute::Block(_) => false,
+    });
+
+    ["script", "style"]
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case(name))
+        && !has_nonce
+}
+
+/// Inline event handlers and `javascript:` urls are how most XSS gets in, so
+/// they're a compile error unless the attribute is marked like
+/// `unsafe:onclick`. With the `strict-csp` feature handlers are an error
+/// either way, since the policy blocks them.
+fn lint_attributes(attributes: &[NodeAttribute]) -> Vec<syn::Error> {
+    let mut errors = vec![];
+    for attr in attributes {
+        let NodeAttribute::Attribute(attr) = attr else {
+            continue;
+        };
+        let key = attr.key.to_string();
+        let allowed = key.starts_with("unsafe:");
+        let name = attr_key(attr);
+        let handler = name.len() > 2
+            && name
+                .get(..2)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("on"));
+        let message = if handler {
+            match allowed {
+                _ if cfg!(feature = "strict-csp") => format!(
+                    "`{}` is an inline event handler, which a strict Content-Security-Policy \
+                     blocks, add the listener from a script instead",
+                    name
+                ),
+                false => format!(
+                    "`{}` is an inline event handler, add the listener from a script or use \
+                     `unsafe:{}` if it's meant to be here",
+                    name, name
+                ),
+                true => continue,
+            }
+        } else if ["href", "xlink:href"].contains(&name.as_str())
+            && !allowed
+            && is_javascript_url(attr)
+        {
+            "`javascript:` urls run whatever they contain, use a button with a listener or \
+             `unsafe:href` if it's meant to be here"
+                .to_owned()
+        } else {
+            continue;
+        };
+        errors.push(syn::Error::new_spanned(&attr.key, message));
     }
+
+    errors
+}
+
+fn is_javascript_url(attr: &KeyedAttribute) -> bool {
+    attr.value_literal_string().is_some_and(|value| {
+        value
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+            .take(11)
+            .collect::<String>()
+            .eq_ignore_ascii_case("javascript:")
+    })
+}
+
+/// The attribute's name as it's rendered, without an `unsafe:` mark.
+fn attr_key(attr: &KeyedAttribute) -> String {
+    let key = attr.key.to_string();
+    match key.strip_prefix("unsafe:") {
+        Some(key) => key.to_owned(),
+        None => key,
+    }
+}
+
+fn render_attributes(output: &mut Output, attributes: &[NodeAttribute]) {
+    for (key, value) in attribute_values(attributes) {
+        output.push_str(" ");
+        output.push_str(&key);
+        match value {
+            AttrPart::Bool => {}
+            AttrPart::Literal(literal) => {
+                output.push_str("=\"");
+                output.push_str(&literal);
+                output.push_str("\"");
+            }
+            AttrPart::Expr(prefix, tokens) => {
+                output.push_str("=\"");
+                output.push_str(&prefix);
+                output.push_tokens(tokens);
+                output.push_str("\"");
+            }
+        }
+    }
+}
+
+/// The value of an attribute written in a template, without a spread.
+enum AttrPart {
+    Bool,
+    /// Written as a string literal, rendered as it is.
+    Literal(String),
+    /// Rendered from an expression, after the static text that comes first,
+    /// like the literal declarations of a merged `style`.
+    Expr(String, TokenStream2),
+}
+
+/// The attributes of an element without a spread, in order, with `class:`
+/// toggles and `style:` declarations merged into the first `class` or `style`.
+fn attribute_values(attributes: &[NodeAttribute]) -> Vec<(String, AttrPart)> {
+    let mut class_list = class_list(attributes);
+    let merge_classes = class_list.is_some();
+    let mut style_list = style_list(attributes);
+    let merge_styles = style_list.is_some();
+    let mut values = vec![];
+    for attr in attributes {
+        match attr {
+            NodeAttribute::Block(_) => {}
+            NodeAttribute::Attribute(attr) if merge_classes && is_class(attr) => {
+                if let Some(tokens) = class_list.take() {
+                    values.push(("class".to_owned(), AttrPart::Expr(String::new(), tokens)));
+                }
+            }
+            NodeAttribute::Attribute(attr) if merge_styles && is_style(attr) => {
+                if let Some((declarations, tokens)) = style_list.take() {
+                    values.push(("style".to_owned(), AttrPart::Expr(declarations, tokens)));
+                }
+            }
+            NodeAttribute::Attribute(attr) => {
+                let value = match (attr.value_literal_string(), attr.value()) {
+                    (Some(literal), _) => AttrPart::Literal(literal),
+                    (None, Some(expr)) => AttrPart::Expr(String::new(), expr.to_token_stream()),
+                    (None, None) => AttrPart::Bool,
+                };
+                values.push((attr_key(attr), value));
+            }
+        }
+    }
+
+    values
+}
+
+/// Collects every attribute of an element with a `{..spread}` into one `Attrs`
+/// expression so spread attributes merge with the ones written in the template.
+/// The CSP nonce goes first when `nonce` is set, so a `nonce` in a spread
+/// replaces it.
+fn attrs(attributes: &[NodeAttribute], nonce: bool) -> TokenStream2 {
+    let mut class_list = class_list(attributes);
+    let merge_classes = class_list.is_some();
+    let mut style_list = style_list(attributes);
+    let merge_styles = style_list.is_some();
+    let attrs = attributes.iter().map(|attr| match attr {
+        NodeAttribute::Block(block) => {
+            let value = spread(block);
+            quote! { .spread(#value) }
+        }
+        NodeAttribute::Attribute(attr) if merge_classes && is_class(attr) => {
+            match class_list.take() {
+                Some(tokens) => quote! { .with("class", #tokens) },
+                None => quote! {},
+            }
+        }
+        NodeAttribute::Attribute(attr) if merge_styles && is_style(attr) => {
+            match style_list.take() {
+                Some((declarations, tokens)) => {
+                    quote! { .with("style", #declarations).with("style", #tokens) }
+                }
+                None => quote! {},
+            }
+        }
+        NodeAttribute::Attribute(attr) => {
+            let key = attr_key(attr);
+            match attr.value() {
+                Some(value) => quote! { .with(#key, #value) },
+                None => quote! { .with(#key, true) },
+            }
+        }
+    });
+    let attrs = attrs.collect::<Vec<_>>();
+    let nonce = nonce.then(|| quote! { .with("nonce", ::shtml::csp::nonce()) });
+
+    quote! { ::shtml::Attrs::new() #nonce #(#attrs)* }
+}
+
+/// Returns `attrs` from a `{..attrs}` block.
+fn spread(block: &NodeBlock) -> Option<TokenStream2> {
+    match block {
+        NodeBlock::ValidBlock(block) => block.stmts.iter().find_map(|stmt| match stmt {
+            syn::Stmt::Expr(syn::Expr::Range(range), _) => {
+                range.end.as_ref().map(|end| end.to_token_stream())
+            }
+            _ => None,
+        }),
+        NodeBlock::Invalid { .. } => None,
+    }
+}
+
+fn is_class(attr: &KeyedAttribute) -> bool {
+    let key = attr.key.to_string();
+    key == "class" || key.starts_with("class:")
+}
+
+/// Merges `class="..."` with any `class:name=bool` toggles on the same element
+/// into a single `Classes` expression, or returns `None` when there are no toggles.
+fn class_list(attributes: &[NodeAttribute]) -> Option<TokenStream2> {
+    let keyed = attributes
+        .iter()
+        .filter_map(|attr| match attr {
+            NodeAttribute::Attribute(attr) if is_class(attr) => Some(attr),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if keyed.iter().all(|attr| attr.key.to_string() == "class") {
+        return None;
+    }
+
+    let classes = keyed.iter().map(|attr| {
+        let key = attr.key.to_string();
+        match key.strip_prefix("class:") {
+            Some(name) => {
+                let value = match attr.value() {
+                    Some(value) => value.to_token_stream(),
+                    None => quote! { true },
+                };
+                quote! { .with((#name, #value)) }
+            }
+            None => {
+                let value = attr.value();
+                quote! { .with(#value) }
+            }
+        }
+    });
+
+    Some(quote! { ::shtml::Classes::new()#(#classes)* })
+}
+
+fn is_style(attr: &KeyedAttribute) -> bool {
+    let key = attr.key.to_string();
+    key == "style" || key.starts_with("style:")
+}
+
+/// Merges `style="..."` with any `style:property=value` declarations on the same
+/// element. Static declarations are kept as written, everything else becomes a
+/// `Style` expression. Returns `None` when there are no `style:` declarations.
+fn style_list(attributes: &[NodeAttribute]) -> Option<(String, TokenStream2)> {
+    let keyed = attributes
+        .iter()
+        .filter_map(|attr| match attr {
+            NodeAttribute::Attribute(attr) if is_style(attr) => Some(attr),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if keyed.iter().all(|attr| attr.key.to_string() == "style") {
+        return None;
+    }
+
+    let mut declarations = String::new();
+    let mut styles = vec![];
+    for attr in keyed {
+        let key = attr.key.to_string();
+        match (key.strip_prefix("style:"), attr.value_literal_string()) {
+            (Some(property), _) => {
+                let value = attr.value();
+                styles.push(quote! { .with(#property, #value) });
+            }
+            (None, Some(literal)) => {
+                let literal = literal.trim().trim_end_matches(';');
+                if !literal.is_empty() {
+                    declarations.push_str(literal);
+                    declarations.push_str("; ");
+                }
+            }
+            (None, None) => {
+                let value = attr.value();
+                styles.push(quote! { .merge(#value) });
+            }
+        }
+    }
+
+    Some((declarations, quote! { ::shtml::Style::new()#(#styles)* }))
 }
 
 #[derive(Debug)]
 struct Output {
     buf: Ident,
     static_string: String,
+    static_len: usize,
+    exprs: Vec<(Ident, TokenStream2)>,
     tokens: Vec<TokenStream2>,
+    whitespace: Whitespace,
+    /// Inside `<svg>` or `<math>`, where names are case sensitive, any empty
+    /// element can self close and there are no components.
+    foreign: bool,
+    /// In `xml!`, where dynamic values are cleaned of characters xml doesn't
+    /// allow.
+    xml: bool,
+    hot_reload: Option<HotReload>,
 }
 
 impl Output {
-    fn new(buf: Ident) -> Self {
+    fn new(buf: Ident, hot_reload: Option<HotReload>) -> Self {
         Self {
             buf,
             tokens: vec![],
+            exprs: vec![],
             static_string: String::new(),
+            static_len: 0,
+            whitespace: Whitespace::Collapse,
+            foreign: false,
+            xml: false,
+            hot_reload,
+        }
+    }
+
+    fn child(&self) -> Self {
+        Self {
+            whitespace: self.whitespace,
+            foreign: self.foreign,
+            xml: self.xml,
+            ..Self::new(
+                self.buf.clone(),
+                self.hot_reload.as_ref().map(HotReload::child),
+            )
         }
     }
 
@@ -256,28 +750,115 @@ impl Output {
     fn push_tokens(&mut self, tokens: TokenStream2) {
         self.push_expr();
         let buf = &self.buf;
-        let tokens = quote! {
-            #tokens.render_to_string(&mut #buf);
-        };
-        self.tokens.push(tokens);
+        let ident = Ident::new(&format!("__shtml_{}", self.exprs.len()), Span::call_site());
+        self.tokens.push(match self.xml {
+            true => quote!(::shtml::xml::render(#ident, &mut #buf);),
+            false => quote!(#ident.render_to_string(&mut #buf);),
+        });
+        self.exprs.push((ident, tokens));
     }
 
     fn push_expr(&mut self) {
         if self.static_string.is_empty() {
             return;
         }
-        let expr = {
-            let output_ident = self.buf.clone();
-            let string = LitStr::new(&self.static_string, Span::call_site());
-            quote!(#output_ident.push_str(#string);)
+        let output_ident = self.buf.clone();
+        let expr = match &self.hot_reload {
+            Some(hot_reload) => {
+                let part = hot_reload.part;
+                let chunk = hot_reload.push(&self.static_string);
+                quote!(#output_ident.push_str(__shtml_chunks.get(#part, #chunk));)
+            }
+            None => {
+                let string = LitStr::new(&self.static_string, Span::call_site());
+                quote!(#output_ident.push_str(#string);)
+            }
         };
+        self.static_len += self.static_string.len();
         self.static_string.clear();
         self.tokens.push(expr);
     }
 
-    fn to_token_stream(mut self) -> TokenStream2 {
+    fn is_empty(&self) -> bool {
+        self.static_string.is_empty() && self.tokens.is_empty()
+    }
+
+    /// Builds the `Component` expression. Dynamic parts are evaluated first, in
+    /// order, so the buffer can be sized from the static byte count plus each
+    /// part's `size_hint`, or the size this call site rendered last time if
+    /// that was bigger.
+    fn into_component(mut self) -> TokenStream2 {
+        self.push_expr();
+        let buf = &self.buf;
+        let static_len = match &self.hot_reload {
+            Some(hot_reload) => {
+                let part = hot_reload.part;
+                quote!(__shtml_chunks.len(#part))
+            }
+            None => self.static_len.to_token_stream(),
+        };
+        let tokens = &self.tokens;
+        let idents = self.exprs.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+        let render = self.bind_exprs(quote! {
+            let __shtml_size_hint = #static_len #(+ #idents.size_hint())*;
+            let mut #buf = String::with_capacity(::core::cmp::max(
+                __shtml_size_hint,
+                __SHTML_SIZE.load(::core::sync::atomic::Ordering::Relaxed),
+            ));
+            #(#tokens)*
+            __SHTML_SIZE.store(#buf.len(), ::core::sync::atomic::Ordering::Relaxed);
+            Component { html: #buf }
+        });
+
+        quote! {
+            {
+                static __SHTML_SIZE: ::core::sync::atomic::AtomicUsize =
+                    ::core::sync::atomic::AtomicUsize::new(0);
+                #render
+            }
+        }
+    }
+
+    /// Evaluates the dynamic parts, in order, and runs `body` with each one
+    /// borrowed as its ident. They're bound by a `match` like `format_args!`
+    /// does, since a `let` would drop temporaries such as the `String` in
+    /// `{name.to_uppercase().as_str()}` before they're rendered.
+    fn bind_exprs(&self, body: TokenStream2) -> TokenStream2 {
+        let idents = self.exprs.iter().map(|(ident, _)| ident);
+        let exprs = self.exprs.iter().map(|(_, expr)| expr);
+
+        quote! {
+            match (#(&(#exprs),)*) {
+                (#(#idents,)*) => { #body }
+            }
+        }
+    }
+
+    /// Like `into_component` but appends to `buffer`, reserving what the parts
+    /// need up front, so nothing else is allocated for the template itself.
+    fn render_into(mut self, buffer: &syn::Expr) -> TokenStream2 {
         self.push_expr();
-        self.tokens.into_iter().collect()
+        let buf = &self.buf;
+        let static_len = match &self.hot_reload {
+            Some(hot_reload) => {
+                let part = hot_reload.part;
+                quote!(__shtml_chunks.len(#part))
+            }
+            None => self.static_len.to_token_stream(),
+        };
+        let tokens = &self.tokens;
+        let idents = self.exprs.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+        let render = self.bind_exprs(quote! {
+            #buf.reserve(#static_len #(+ #idents.size_hint())*);
+            #(#tokens)*
+        });
+
+        quote! {
+            {
+                let mut #buf: &mut ::shtml::__private::String = #buffer;
+                #render
+            }
+        }
     }
 }
 
@@ -289,3 +870,38 @@ pub fn component(_args: TokenStream, input: TokenStream) -> TokenStream {
         Err(e) => e.to_compile_error().into(),
     }
 }
+
+#[proc_macro_derive(Render, attributes(render))]
+pub fn derive_render(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match derive::render_macro(input) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[cfg(feature = "i18n")]
+#[proc_macro]
+pub fn t(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as i18n::Translate);
+    match i18n::t_macro(input) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Escapes text that is known when the macro expands.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
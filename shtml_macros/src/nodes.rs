@@ -0,0 +1,261 @@
+use crate::{
+    attribute_values, attrs, component_call, component_name, end_of, lint_attributes, needs_nonce,
+    parser, provider_value, start_of,
+    whitespace::{self, node_range, Whitespace},
+    AttrPart, Output,
+};
+use proc_macro2::{LineColumn, Span, TokenStream as TokenStream2};
+use quote::quote;
+use rstml::node::{Node, NodeAttribute, NodeElement};
+use syn::{Ident, LitStr, Result};
+
+/// Builds the `Vec<Node>` for a template while walking it, like `html!`
+/// builds its string: elements, text and comments become nodes right away
+/// and `{expr}` and components add theirs with `Render::render_nodes`.
+/// Whitespace, attributes and nonces follow `html!`'s rules, so the nodes
+/// render to the same html.
+pub fn nodes_macro(input: TokenStream2) -> Result<TokenStream2> {
+    let nodes = parser(false).parse_simple(input)?;
+    let mut tree = Tree::new(Whitespace::Collapse, false);
+    tree.nodes(&nodes, None, None);
+
+    Ok(tree.into_nodes())
+}
+
+struct Tree {
+    whitespace: Whitespace,
+    /// Inside `<svg>` or `<math>`, like `Output::foreign`.
+    foreign: bool,
+    /// Static text that hasn't been pushed yet, so text and the whitespace
+    /// around it are pushed at once.
+    text: String,
+    tokens: Vec<TokenStream2>,
+}
+
+impl Tree {
+    fn new(whitespace: Whitespace, foreign: bool) -> Self {
+        Self {
+            whitespace,
+            foreign,
+            text: String::new(),
+            tokens: vec![],
+        }
+    }
+
+    fn push_str(&mut self, string: &str) {
+        self.text.push_str(string);
+    }
+
+    fn push_tokens(&mut self, tokens: TokenStream2) {
+        self.push_text();
+        self.tokens.push(tokens);
+    }
+
+    fn push_text(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        let text = LitStr::new(&std::mem::take(&mut self.text), Span::call_site());
+        self.tokens
+            .push(quote!(::shtml::__private::push_text(__shtml_nodes, #text);));
+    }
+
+    /// The statements that add the nodes to `__shtml_nodes`.
+    fn into_statements(mut self) -> TokenStream2 {
+        self.push_text();
+        let tokens = self.tokens;
+        quote!(#(#tokens)*)
+    }
+
+    /// The `Vec<Node>` expression.
+    fn into_nodes(self) -> TokenStream2 {
+        let statements = self.into_statements();
+        quote! {
+            {
+                let mut __shtml_root = ::shtml::__private::Vec::<::shtml::Node>::new();
+                {
+                    let __shtml_nodes = &mut __shtml_root;
+                    #statements
+                }
+                __shtml_root
+            }
+        }
+    }
+
+    /// Adds sibling nodes along with the whitespace between them, like
+    /// `render_nodes`.
+    fn nodes(&mut self, nodes: &[Node], open: Option<LineColumn>, close: Option<LineColumn>) {
+        let mut end = open;
+        for (i, node) in nodes.iter().enumerate() {
+            let range = node_range(node);
+            if let (Some(end), Some((start, _))) = (end, range) {
+                let mut gap = String::new();
+                match i {
+                    0 => self.whitespace.leading(&mut gap, end, start),
+                    _ => self.whitespace.gap(&mut gap, end, start),
+                }
+                self.push_str(&gap);
+            }
+            self.node(node);
+            end = range.map(|(_, end)| end);
+        }
+
+        if let (Some(end), Some(close), false) = (end, close, nodes.is_empty()) {
+            let mut gap = String::new();
+            self.whitespace.trailing(&mut gap, end, close);
+            self.push_str(&gap);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Comment(c) => {
+                let comment = c.value.value();
+                self.push_tokens(quote! {
+                    __shtml_nodes.push(::shtml::Node::Comment(
+                        ::shtml::__private::String::from(#comment),
+                    ));
+                });
+            }
+            Node::Doctype(d) => {
+                let doctype = format!("DOCTYPE {}", d.value.to_token_stream_string());
+                self.push_tokens(quote! {
+                    __shtml_nodes.push(::shtml::Node::Doctype(
+                        ::shtml::__private::String::from(#doctype),
+                    ));
+                });
+            }
+            Node::Fragment(n) => self.nodes(
+                &n.children,
+                end_of(&n.tag_open),
+                n.tag_close.as_ref().and_then(start_of),
+            ),
+            Node::Element(n) => match component_name(n).filter(|_| !self.foreign) {
+                Some(fn_name) if fn_name == "Provider" => {
+                    let value = match provider_value(fn_name, &n.open_tag.attributes) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            self.push_tokens(e.to_compile_error());
+                            return;
+                        }
+                    };
+
+                    let mut children = Tree::new(self.whitespace, self.foreign);
+                    children.nodes(
+                        &n.children,
+                        end_of(&n.open_tag),
+                        n.close_tag.as_ref().and_then(start_of),
+                    );
+                    let children = children.into_nodes();
+
+                    self.push_tokens(quote! {
+                        __shtml_nodes.extend(::shtml::Provider(#value, || #children));
+                    });
+                }
+                Some(fn_name) => {
+                    // components take their children as a `Component`
+                    let buf = Ident::new("__shtml_buf", Span::call_site());
+                    let mut children = Output::new(buf, None);
+                    children.whitespace = self.whitespace;
+                    let call = component_call(children, fn_name, n);
+
+                    self.push_tokens(quote! {
+                        ::shtml::Render::render_nodes(&#call, __shtml_nodes);
+                    });
+                }
+                None => self.element(n),
+            },
+            Node::Block(n) => self.push_tokens(quote! {
+                ::shtml::Render::render_nodes(&(#n), __shtml_nodes);
+            }),
+            Node::Text(n) => self.push_str(&n.value_string()),
+            Node::RawText(n) => self.push_str(&whitespace::raw_text(n, self.whitespace)),
+        }
+    }
+
+    fn element(&mut self, n: &NodeElement) {
+        let name = n.open_tag.name.to_string();
+        for error in lint_attributes(&n.open_tag.attributes) {
+            self.push_tokens(error.to_compile_error());
+        }
+        let attributes = attributes(&name, &n.open_tag.attributes);
+
+        // `html!` closes empty elements with `/>` unless they have a closing
+        // tag outside of svg and math
+        let foreign = self.foreign || ["svg", "math"].contains(&name.as_str());
+        let self_closing = n.children.is_empty() && (n.close_tag.is_none() || foreign);
+
+        let mut children = Tree::new(self.whitespace, foreign && name != "foreignObject");
+        let open = end_of(&n.open_tag);
+        if let (Whitespace::Collapse, Some(open), true) =
+            (self.whitespace, open, Whitespace::preserves(&name))
+        {
+            children.whitespace = Whitespace::Preserve {
+                indent: whitespace::indent(&n.children, open),
+            };
+        }
+        children.nodes(&n.children, open, n.close_tag.as_ref().and_then(start_of));
+        let children = children.into_statements();
+
+        self.push_tokens(quote! {
+            {
+                let mut __shtml_element = ::shtml::Element::new(#name);
+                __shtml_element.self_closing = #self_closing;
+                #attributes
+                {
+                    let __shtml_nodes = &mut __shtml_element.children;
+                    #children
+                }
+                __shtml_nodes.push(::shtml::Node::Element(__shtml_element));
+            }
+        });
+    }
+}
+
+/// Pushes an element's attributes onto `__shtml_element.attrs`, with the same
+/// values `html!` renders.
+fn attributes(name: &str, attributes: &[NodeAttribute]) -> TokenStream2 {
+    let nonce = needs_nonce(name, attributes);
+    let has_spread = attributes
+        .iter()
+        .any(|attr| matches!(attr, NodeAttribute::Block(_)));
+    let push_all = |attrs: TokenStream2| {
+        quote! {
+            for (__shtml_key, __shtml_value) in #attrs {
+                __shtml_element.attrs.push((__shtml_key.into_owned(), __shtml_value));
+            }
+        }
+    };
+    if has_spread {
+        return push_all(attrs(attributes, nonce));
+    }
+
+    let mut tokens = vec![];
+    if nonce {
+        tokens.push(push_all(quote!(
+            ::shtml::Attrs::new().with("nonce", ::shtml::csp::nonce())
+        )));
+    }
+    for (key, value) in attribute_values(attributes) {
+        let value = match value {
+            AttrPart::Bool => quote!(::core::option::Option::None),
+            AttrPart::Literal(literal) => {
+                quote!(::core::option::Option::Some(::shtml::__private::String::from(#literal)))
+            }
+            AttrPart::Expr(prefix, expr) => quote! {
+                {
+                    let mut __shtml_value = ::shtml::__private::String::from(#prefix);
+                    ::shtml::Render::render_to_string(&(#expr), &mut __shtml_value);
+                    ::core::option::Option::Some(__shtml_value)
+                }
+            },
+        };
+        tokens.push(quote! {
+            __shtml_element
+                .attrs
+                .push((::shtml::__private::String::from(#key), #value));
+        });
+    }
+
+    quote!(#(#tokens)*)
+}
@@ -0,0 +1,130 @@
+use proc_macro2::{LineColumn, TokenStream as TokenStream2, TokenTree};
+use quote::ToTokens;
+use rstml::node::{Node, RawText};
+
+/// How whitespace in the template ends up in the html.
+///
+/// `Collapse` is used everywhere except `<pre>` and `<textarea>`: whitespace
+/// on a single line becomes one space, and whitespace containing a line break
+/// is removed next to a tag or `{expr}` but becomes one space inside text,
+/// like jsx. `Preserve` keeps line breaks and indentation relative
+/// to the least indented line of the element's content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Whitespace {
+    Collapse,
+    Preserve { indent: usize },
+}
+
+impl Whitespace {
+    pub fn preserves(element: &str) -> bool {
+        matches!(element, "pre" | "textarea")
+    }
+
+    /// Whitespace between two nodes, or two tokens of unquoted text.
+    pub fn gap(self, string: &mut String, end: LineColumn, start: LineColumn) {
+        match self {
+            Whitespace::Collapse => {
+                if end.line == start.line && start.column > end.column {
+                    string.push(' ');
+                }
+            }
+            Whitespace::Preserve { indent } => match end.line == start.line {
+                true => push_spaces(string, start.column.saturating_sub(end.column)),
+                false => {
+                    (end.line..start.line).for_each(|_| string.push('\n'));
+                    push_spaces(string, start.column.saturating_sub(indent));
+                }
+            },
+        }
+    }
+
+    /// Whitespace between an opening tag and its first child. A line break
+    /// right after the tag is dropped.
+    pub fn leading(self, string: &mut String, end: LineColumn, start: LineColumn) {
+        match (self, end.line == start.line) {
+            (_, true) => self.gap(string, end, start),
+            (Whitespace::Preserve { indent }, false) => {
+                push_spaces(string, start.column.saturating_sub(indent))
+            }
+            (Whitespace::Collapse, false) => {}
+        }
+    }
+
+    /// Whitespace between the last child and a closing tag. A line break
+    /// right before the tag is dropped.
+    pub fn trailing(self, string: &mut String, end: LineColumn, start: LineColumn) {
+        if end.line == start.line {
+            self.gap(string, end, start);
+        }
+    }
+}
+
+fn push_spaces(string: &mut String, count: usize) {
+    (0..count).for_each(|_| string.push(' '));
+}
+
+/// Where `tokens` start and end in the source file, if the compiler knows.
+pub fn span_range(tokens: TokenStream2) -> Option<(LineColumn, LineColumn)> {
+    let mut tokens = tokens.into_iter();
+    let first = tokens.next()?;
+    let last = tokens.last().unwrap_or_else(|| first.clone());
+    let (start, end) = (first.span().start(), last.span().end());
+    match start.line {
+        0 => None,
+        _ => Some((start, end)),
+    }
+}
+
+pub fn node_range(node: &Node) -> Option<(LineColumn, LineColumn)> {
+    span_range(node.to_token_stream())
+}
+
+/// Renders unquoted text from the source so punctuation stays where it was
+/// written, falling back to the token stream's formatting when the source
+/// isn't available.
+pub fn raw_text(text: &RawText, whitespace: Whitespace) -> String {
+    let tokens = text.to_token_stream().into_iter().collect::<Vec<TokenTree>>();
+    let mut string = String::new();
+    let mut end: Option<LineColumn> = None;
+    for token in tokens {
+        let span = token.span();
+        let (start, source) = match (span.start(), span.source_text()) {
+            (start, Some(source)) if start.line > 0 => (start, source),
+            _ => return text.to_token_stream_string(),
+        };
+        match (end, whitespace) {
+            (Some(end), Whitespace::Collapse) if end.line != start.line => string.push(' '),
+            (Some(end), _) => whitespace.gap(&mut string, end, start),
+            (None, _) => {}
+        }
+        match whitespace {
+            Whitespace::Collapse => string.push_str(&collapse(&source)),
+            Whitespace::Preserve { .. } => string.push_str(&source),
+        }
+        end = Some(span.end());
+    }
+
+    string
+}
+
+fn collapse(source: &str) -> String {
+    source.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The smallest column a line of `nodes` starts at, which `<pre>` content is
+/// dedented by.
+pub fn indent(nodes: &[Node], open: LineColumn) -> usize {
+    let mut line = open.line;
+    let mut indent: Option<usize> = None;
+    for token in nodes.iter().flat_map(|node| node.to_token_stream()) {
+        let span = token.span();
+        if span.start().line > line {
+            indent = Some(indent.map_or(span.start().column, |indent| {
+                indent.min(span.start().column)
+            }));
+        }
+        line = span.end().line;
+    }
+
+    indent.unwrap_or(0)
+}
@@ -0,0 +1,51 @@
+use crate::{escape, hot_reload, html_component};
+use proc_macro2::TokenStream as TokenStream2;
+use rstml::node::NodeName;
+use syn::{
+    parse::{Parse, ParseStream},
+    LitStr, Result, Token,
+};
+
+/// The `<?xml version="1.0"?>` style processing instructions at the start of
+/// `xml!`, which rstml can't parse, and the markup after them.
+pub struct Prolog {
+    instructions: String,
+    markup: TokenStream2,
+}
+
+impl Parse for Prolog {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut instructions = String::new();
+        while input.peek(Token![<]) && input.peek2(Token![?]) {
+            input.parse::<Token![<]>()?;
+            input.parse::<Token![?]>()?;
+            let name = input.parse::<NodeName>()?;
+            instructions.push_str(&format!("<?{}", name));
+            while !input.peek(Token![?]) {
+                let key = input.parse::<NodeName>()?;
+                input.parse::<Token![=]>()?;
+                let value = input.parse::<LitStr>()?;
+                instructions.push_str(&format!(" {}=\"{}\"", key, escape(&value.value())));
+            }
+            input.parse::<Token![?]>()?;
+            input.parse::<Token![>]>()?;
+            instructions.push_str("?>");
+        }
+
+        Ok(Self {
+            instructions,
+            markup: input.parse()?,
+        })
+    }
+}
+
+/// Renders markup like `html!` but as xml: every empty element self closes,
+/// names like `atom:link` are never components and nothing is a void element.
+pub fn xml_macro(input: Prolog) -> Result<TokenStream2> {
+    html_component(
+        input.markup,
+        &hot_reload::call_site(),
+        Some(&input.instructions),
+        None,
+    )
+}
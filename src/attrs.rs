@@ -0,0 +1,274 @@
+use crate::{escape, Classes, Component, Fmt, Number, Render, Style};
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+/// Html attributes that can be spread onto elements and components with `{..attrs}`.
+///
+/// Keys that aren't valid attribute names are skipped. Setting a key twice
+/// appends to `class` and `style` and replaces the value of anything else.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Attrs<'a> {
+    attrs: Vec<(Cow<'a, str>, Option<String>)>,
+}
+
+impl<'a> Attrs<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key: impl Into<Cow<'a, str>>, value: impl AttrValue) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn insert(&mut self, key: impl Into<Cow<'a, str>>, value: impl AttrValue) {
+        value.insert_into(key.into(), self);
+    }
+
+    pub fn spread(mut self, attrs: impl IntoAttrs<'a>) -> Self {
+        attrs.into_attrs(&mut self);
+        self
+    }
+
+    /// Returns the escaped value of `key`, or `Some("")` for a boolean attribute.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_deref().unwrap_or(""))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attrs.is_empty()
+    }
+
+    /// Sets an already escaped value, `None` being a boolean attribute.
+    pub fn set(&mut self, key: Cow<'a, str>, value: Option<String>) {
+        if !is_valid_key(&key) {
+            return;
+        }
+
+        match self.attrs.iter_mut().find(|(k, _)| *k == key) {
+            Some((k, existing)) => match (k.as_ref(), existing, value) {
+                ("class", Some(existing), Some(value)) => {
+                    for class in value.split_whitespace() {
+                        if !existing.split_whitespace().any(|c| c == class) {
+                            if !existing.is_empty() {
+                                existing.push(' ');
+                            }
+                            existing.push_str(class);
+                        }
+                    }
+                }
+                ("style", Some(existing), Some(value)) => {
+                    let declarations = existing.trim_end().trim_end_matches(';').len();
+                    existing.truncate(declarations);
+                    if !existing.is_empty() {
+                        existing.push_str("; ");
+                    }
+                    existing.push_str(&value);
+                }
+                (_, existing, value) => *existing = value,
+            },
+            None => self.attrs.push((key, value)),
+        }
+    }
+}
+
+impl<'a> IntoIterator for Attrs<'a> {
+    type Item = (Cow<'a, str>, Option<String>);
+    type IntoIter = alloc::vec::IntoIter<Self::Item>;
+
+    /// The keys and escaped values, `None` being a boolean attribute.
+    fn into_iter(self) -> Self::IntoIter {
+        self.attrs.into_iter()
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '<' | '>' | '/' | '=')
+        })
+}
+
+impl Render for Attrs<'_> {
+    fn render_to_string(&self, buffer: &mut String) {
+        for (key, value) in &self.attrs {
+            buffer.push(' ');
+            buffer.push_str(key);
+            if let Some(value) = value {
+                buffer.push_str("=\"");
+                buffer.push_str(value);
+                buffer.push('"');
+            }
+        }
+    }
+}
+
+/// Values that can be stored in `Attrs`.
+///
+/// `true` renders a boolean attribute, `false` and `None` leave the attribute out.
+pub trait AttrValue {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>);
+}
+
+impl<T: AttrValue + ?Sized> AttrValue for &T {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        (**self).insert_into(key, attrs);
+    }
+}
+
+impl<T: AttrValue> AttrValue for Option<T> {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        if let Some(value) = self {
+            value.insert_into(key, attrs);
+        }
+    }
+}
+
+impl AttrValue for bool {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        if *self {
+            attrs.set(key, None);
+        }
+    }
+}
+
+impl AttrValue for str {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        attrs.set(key, Some(escape(self).into_owned()));
+    }
+}
+
+impl AttrValue for String {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        self.as_str().insert_into(key, attrs);
+    }
+}
+
+macro_rules! impl_attr_value_render {
+    ($t:ty) => {
+        impl AttrValue for $t {
+            fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+                let mut value = String::new();
+                self.render_to_string(&mut value);
+                attrs.set(key, Some(value));
+            }
+        }
+    };
+}
+
+impl_attr_value_render!(u8);
+impl_attr_value_render!(i8);
+impl_attr_value_render!(u16);
+impl_attr_value_render!(i16);
+impl_attr_value_render!(i64);
+impl_attr_value_render!(u64);
+impl_attr_value_render!(i32);
+impl_attr_value_render!(u32);
+impl_attr_value_render!(usize);
+impl_attr_value_render!(isize);
+impl_attr_value_render!(f64);
+impl_attr_value_render!(f32);
+impl_attr_value_render!(Component);
+impl_attr_value_render!(Classes<'_>);
+impl_attr_value_render!(Style<'_>);
+impl_attr_value_render!(Number<'_>);
+
+impl<T: fmt::Display> AttrValue for Fmt<T> {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        let mut value = String::new();
+        self.render_to_string(&mut value);
+        attrs.set(key, Some(value));
+    }
+}
+
+/// Anything that can be spread with `{..attrs}`.
+pub trait IntoAttrs<'a> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>);
+}
+
+impl<'a> IntoAttrs<'a> for Attrs<'a> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.attrs
+            .into_iter()
+            .for_each(|(key, value)| attrs.set(key, value));
+    }
+}
+
+impl<'a> IntoAttrs<'a> for &'a Attrs<'_> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.attrs
+            .iter()
+            .for_each(|(key, value)| attrs.set(Cow::Borrowed(key), value.clone()));
+    }
+}
+
+impl<'a, K: AsRef<str>, V: AttrValue> IntoAttrs<'a> for &'a [(K, V)] {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.iter()
+            .for_each(|(key, value)| attrs.insert(key.as_ref(), value));
+    }
+}
+
+impl<'a, K: AsRef<str>, V: AttrValue, const N: usize> IntoAttrs<'a> for &'a [(K, V); N] {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.as_slice().into_attrs(attrs);
+    }
+}
+
+impl<'a, K: AsRef<str>, V: AttrValue> IntoAttrs<'a> for &'a Vec<(K, V)> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.as_slice().into_attrs(attrs);
+    }
+}
+
+impl<'a, K: AsRef<str>, V: AttrValue> IntoAttrs<'a> for &'a BTreeMap<K, V> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.iter()
+            .for_each(|(key, value)| attrs.insert(key.as_ref(), value));
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, K: AsRef<str>, V: AttrValue, S> IntoAttrs<'a> for &'a std::collections::HashMap<K, V, S> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.iter()
+            .for_each(|(key, value)| attrs.insert(key.as_ref(), value));
+    }
+}
+
+impl<'a, K: ToString, V: AttrValue> IntoAttrs<'a> for Vec<(K, V)> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.into_iter()
+            .for_each(|(key, value)| attrs.insert(key.to_string(), value));
+    }
+}
+
+impl<'a, K: ToString, V: AttrValue, const N: usize> IntoAttrs<'a> for [(K, V); N] {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.into_iter()
+            .for_each(|(key, value)| attrs.insert(key.to_string(), value));
+    }
+}
+
+impl<'a, K: ToString, V: AttrValue> IntoAttrs<'a> for BTreeMap<K, V> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.into_iter()
+            .for_each(|(key, value)| attrs.insert(key.to_string(), value));
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, K: ToString, V: AttrValue, S> IntoAttrs<'a> for std::collections::HashMap<K, V, S> {
+    fn into_attrs(self, attrs: &mut Attrs<'a>) {
+        self.into_iter()
+            .for_each(|(key, value)| attrs.insert(key.to_string(), value));
+    }
+}
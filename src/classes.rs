@@ -0,0 +1,91 @@
+use crate::{escape, Render};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+/// Builds `Classes` from static strings, `(name, bool)` pairs and options.
+#[macro_export]
+macro_rules! classes {
+    ($($class:expr),* $(,)?) => {
+        $crate::Classes::new()$(.with($class))*
+    };
+}
+
+/// A space separated list of class names without duplicates.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Classes<'a> {
+    classes: Vec<Cow<'a, str>>,
+}
+
+impl<'a> Classes<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, class: impl Class<'a>) -> Self {
+        class.add_to(&mut self);
+        self
+    }
+
+    pub fn push(&mut self, class: impl Into<Cow<'a, str>>) {
+        let class = class.into();
+        if !class.is_empty() && !self.classes.contains(&class) {
+            self.classes.push(class);
+        }
+    }
+}
+
+/// Anything that can contribute class names to `Classes`.
+pub trait Class<'a> {
+    fn add_to(self, classes: &mut Classes<'a>);
+}
+
+impl<'a> Class<'a> for &'a str {
+    fn add_to(self, classes: &mut Classes<'a>) {
+        self.split_whitespace().for_each(|class| classes.push(class));
+    }
+}
+
+impl<'a> Class<'a> for &'a String {
+    fn add_to(self, classes: &mut Classes<'a>) {
+        self.as_str().add_to(classes);
+    }
+}
+
+impl<'a> Class<'a> for String {
+    fn add_to(self, classes: &mut Classes<'a>) {
+        self.split_whitespace()
+            .for_each(|class| classes.push(String::from(class)));
+    }
+}
+
+impl<'a, T: Class<'a>> Class<'a> for (T, bool) {
+    fn add_to(self, classes: &mut Classes<'a>) {
+        if self.1 {
+            self.0.add_to(classes);
+        }
+    }
+}
+
+impl<'a, T: Class<'a>> Class<'a> for Option<T> {
+    fn add_to(self, classes: &mut Classes<'a>) {
+        if let Some(class) = self {
+            class.add_to(classes);
+        }
+    }
+}
+
+impl<'a> Class<'a> for Classes<'a> {
+    fn add_to(self, classes: &mut Classes<'a>) {
+        self.classes.into_iter().for_each(|class| classes.push(class));
+    }
+}
+
+impl Render for Classes<'_> {
+    fn render_to_string(&self, buffer: &mut String) {
+        for (i, class) in self.classes.iter().enumerate() {
+            if i > 0 {
+                buffer.push(' ');
+            }
+            buffer.push_str(&escape(class.as_ref()));
+        }
+    }
+}
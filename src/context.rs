@@ -0,0 +1,39 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::{any::Any, cell::RefCell};
+
+std::thread_local! {
+    static CONTEXT: RefCell<Vec<Rc<dyn Any>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Makes `value` available to every component rendered by `elements`.
+///
+/// `html!` calls this for `<Provider value=...>` and wraps the children in a
+/// closure so they are rendered while the value is in scope. A component
+/// that takes `elements: Elements` and puts them in a `<Provider>` can't
+/// provide anything to them, since they were rendered before it was called.
+pub fn Provider<T: 'static, R>(value: T, elements: impl FnOnce() -> R) -> R {
+    CONTEXT.with(|context| context.borrow_mut().push(Rc::new(value)));
+    let _scope = Scope;
+    elements()
+}
+
+/// Returns the nearest value of type `T` provided by an enclosing `<Provider>`.
+pub fn use_context<T: 'static>() -> Option<Rc<T>> {
+    CONTEXT.with(|context| {
+        context
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|value| value.clone().downcast::<T>().ok())
+    })
+}
+
+struct Scope;
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CONTEXT.with(|context| {
+            context.borrow_mut().pop();
+        });
+    }
+}
@@ -0,0 +1,44 @@
+use crate::{escape_into, AttrValue, Attrs, Render};
+use alloc::{borrow::Cow, rc::Rc, string::String};
+
+/// The Content-Security-Policy nonce for the current request. `html!` adds it
+/// to every `<script>` and `<style>` rendered inside a
+/// `<Provider value=Nonce(...)>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nonce(pub String);
+
+/// The ` nonce="..."` attribute `html!` puts on `<script>` and `<style>`,
+/// empty outside of a nonce provider.
+#[doc(hidden)]
+pub struct NonceAttr(Option<Rc<Nonce>>);
+
+#[doc(hidden)]
+pub fn nonce() -> NonceAttr {
+    #[cfg(feature = "std")]
+    return NonceAttr(crate::use_context::<Nonce>());
+
+    #[cfg(not(feature = "std"))]
+    NonceAttr(None)
+}
+
+impl Render for NonceAttr {
+    fn render_to_string(&self, buffer: &mut String) {
+        if let Some(nonce) = &self.0 {
+            buffer.push_str(" nonce=\"");
+            let _ = escape_into(&nonce.0, buffer);
+            buffer.push('"');
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.as_ref().map_or(0, |nonce| nonce.0.len() + 9)
+    }
+}
+
+impl AttrValue for NonceAttr {
+    fn insert_into<'a>(&self, key: Cow<'a, str>, attrs: &mut Attrs<'a>) {
+        if let Some(nonce) = &self.0 {
+            nonce.0.insert_into(key, attrs);
+        }
+    }
+}
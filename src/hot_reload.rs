@@ -0,0 +1,128 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use std::{
+    fs,
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
+/// Every `html!` call's manifest and what was last read from it.
+static SITES: Mutex<BTreeMap<&'static str, Site>> = Mutex::new(BTreeMap::new());
+
+#[derive(Default)]
+struct Site {
+    modified: Option<SystemTime>,
+    chunks: Option<Arc<Vec<Vec<String>>>>,
+    stale: bool,
+}
+
+/// True when markup was edited around code that changed too, so the running
+/// program can't pick it up and needs to be rebuilt. Dev servers can poll this
+/// to decide between reloading the page and restarting.
+pub fn needs_rebuild() -> bool {
+    SITES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .any(|site| site.stale)
+}
+
+#[doc(hidden)]
+pub enum Chunks {
+    Static(&'static [&'static [&'static str]]),
+    Reloaded(Arc<Vec<Vec<String>>>),
+}
+
+impl Chunks {
+    pub fn get(&self, part: usize, chunk: usize) -> &str {
+        match self {
+            Chunks::Static(parts) => parts[part][chunk],
+            Chunks::Reloaded(parts) => &parts[part][chunk],
+        }
+    }
+
+    /// The byte count of a part's chunks, for sizing the buffer.
+    pub fn len(&self, part: usize) -> usize {
+        match self {
+            Chunks::Static(parts) => parts[part].iter().map(|chunk| chunk.len()).sum(),
+            Chunks::Reloaded(parts) => parts[part].iter().map(String::len).sum(),
+        }
+    }
+}
+
+/// Called by `html!` with the hot reload feature to get the static chunks of
+/// a call, from its manifest when it changed since the build and still has
+/// the same structure, otherwise the ones compiled in.
+#[doc(hidden)]
+pub fn chunks(
+    manifest: &'static str,
+    structure: u64,
+    compiled: &'static [&'static [&'static str]],
+) -> Chunks {
+    if !cfg!(debug_assertions) {
+        return Chunks::Static(compiled);
+    }
+
+    let modified = fs::metadata(manifest)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let mut sites = SITES.lock().unwrap_or_else(PoisonError::into_inner);
+    let site = sites.entry(manifest).or_default();
+    if site.modified != modified {
+        site.modified = modified;
+        let parts = fs::read_to_string(manifest)
+            .ok()
+            .and_then(|manifest| read_manifest(&manifest));
+        (site.chunks, site.stale) = match parts {
+            Some((hash, parts)) if hash == structure && same_shape(&parts, compiled) => {
+                (Some(Arc::new(parts)), false)
+            }
+            Some(_) => (None, true),
+            None => (None, false),
+        };
+    }
+
+    match &site.chunks {
+        Some(parts) => Chunks::Reloaded(parts.clone()),
+        None => Chunks::Static(compiled),
+    }
+}
+
+fn same_shape(parts: &[Vec<String>], compiled: &[&[&str]]) -> bool {
+    parts.len() == compiled.len()
+        && parts
+            .iter()
+            .zip(compiled)
+            .all(|(chunks, compiled)| chunks.len() == compiled.len())
+}
+
+/// Reads the structure hash and chunks written by the macro: the hash on the
+/// first line, then every part as its chunk count followed by each chunk's
+/// byte length and the chunk itself.
+fn read_manifest(manifest: &str) -> Option<(u64, Vec<Vec<String>>)> {
+    fn line<'a>(rest: &mut &'a str) -> Option<&'a str> {
+        let (line, tail) = rest.split_once('\n')?;
+        *rest = tail;
+        Some(line)
+    }
+
+    let mut rest = manifest;
+    let structure = line(&mut rest)?.parse().ok()?;
+    let mut parts = Vec::new();
+    while !rest.is_empty() {
+        let count = line(&mut rest)?.parse::<usize>().ok()?;
+        let mut chunks = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = line(&mut rest)?.parse::<usize>().ok()?;
+            chunks.push(rest.get(..len)?.to_string());
+            rest = &rest[len..];
+        }
+        parts.push(chunks);
+    }
+
+    Some((structure, parts))
+}
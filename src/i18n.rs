@@ -0,0 +1,42 @@
+use crate::use_context;
+use alloc::string::String;
+
+/// The locale `t!` translates into, provided to the components that need it
+/// with `<Provider value=Locale("fr".into())>`. Without one, or for messages
+/// a locale doesn't have, the default locale is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub String);
+
+/// Called by `t!` with the locales that have the message, default first, to
+/// pick one for the locale in context: the exact locale, then its language
+/// (`fr` for `fr-CA`), then the default.
+#[doc(hidden)]
+pub fn select(locales: &[&str]) -> usize {
+    let Some(locale) = use_context::<Locale>() else {
+        return 0;
+    };
+    let locale = locale.0.as_str();
+    locales
+        .iter()
+        .position(|l| l.eq_ignore_ascii_case(locale))
+        .or_else(|| {
+            locales
+                .iter()
+                .position(|l| l.eq_ignore_ascii_case(language(locale)))
+        })
+        .unwrap_or(0)
+}
+
+/// Whether `count` takes the `one` plural form. French and Portuguese use it
+/// for zero too, other languages only for one.
+#[doc(hidden)]
+pub fn is_one(locale: &str, count: i64) -> bool {
+    match language(locale) {
+        "fr" | "pt" => count == 0 || count == 1,
+        _ => count == 1,
+    }
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
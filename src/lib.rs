@@ -2,10 +2,128 @@
 #![no_std]
 
 extern crate alloc;
+// tests always have std, for the allocation counter
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+// lets `::shtml` paths in macro output work in this crate's own tests
+extern crate self as shtml;
+
 use alloc::{borrow::Cow, string::String, vec::Vec};
 use core::fmt;
 
-pub use shtml_macros::html;
+pub use shtml_macros::{html, html_into, include_html, nodes, xml, Render};
+
+/// Paths used by macro output that must resolve in `no_std` crates too.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::node::push_text;
+    pub use alloc::{string::String, vec::Vec};
+}
+
+mod attrs;
+pub use attrs::{AttrValue, Attrs, IntoAttrs};
+
+mod classes;
+pub use classes::{Class, Classes};
+
+pub mod csp;
+pub use csp::Nonce;
+
+mod style;
+pub use style::{escape_css, Style};
+
+mod node;
+pub use node::{parse, Element, Node};
+
+mod number;
+pub use number::Number;
+
+mod pretty;
+
+mod sanitize;
+pub use sanitize::Sanitizer;
+
+#[doc(hidden)]
+pub mod xml;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+
+#[cfg(feature = "std")]
+mod context;
+#[cfg(feature = "std")]
+pub use context::{use_context, Provider};
+
+#[cfg(feature = "i18n")]
+pub mod i18n;
+#[cfg(feature = "i18n")]
+pub use {i18n::Locale, shtml_macros::t};
+
+#[cfg(feature = "markdown")]
+mod markdown;
+#[cfg(feature = "markdown")]
+pub use markdown::Markdown;
+
+/// Markup `html!` refuses to compile, checked by the doctests below since
+/// they can't be unit tests.
+///
+/// ```
+/// use shtml::{html, Component, Render};
+///
+/// html! { <button>Save</button><a href="/save">Save</a> };
+/// ```
+///
+/// ```compile_fail
+/// use shtml::{html, Component, Render};
+///
+/// html! { <button onclick="save()">Save</button> };
+/// ```
+///
+/// ```compile_fail
+/// use shtml::{html, Component, Render};
+///
+/// html! { <a href=" JavaScript:save()">Save</a> };
+/// ```
+///
+/// ```compile_fail
+/// use shtml::{html, Component, Render};
+///
+/// html! { <Provider>"no value"</Provider> };
+/// ```
+///
+/// ```compile_fail
+/// use shtml::{html, Component, Render};
+///
+/// html! { <Provider theme="dark">"not a value"</Provider> };
+/// ```
+///
+/// Errors in templates read by `include_html!` point at the template's lines.
+///
+/// ```compile_fail
+/// use shtml::{include_html, Component, Render};
+///
+/// include_html!("tests/templates/undefined.html");
+/// ```
+///
+/// ```compile_fail
+/// use shtml::{include_html, Component, Render};
+///
+/// include_html!("tests/templates/onclick.html");
+/// ```
+#[cfg_attr(
+    feature = "strict-csp",
+    doc = r#"
+```compile_fail
+use shtml::{html, Component, Render};
+
+html! { <button unsafe:onclick="save()">Save</button> };
+```"#
+)]
+#[cfg(doctest)]
+pub struct CompileFail;
 
 #[cfg(not(feature = "chaos"))]
 #[cfg(test)]
@@ -309,6 +427,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn it_works_with_floats() {
         let x = 3.14;
         let result = html! { <div>{x}</div> }.to_string();
@@ -348,6 +467,1215 @@ mod tests {
 
         assert_eq!(result, r#"<div data-test="test">Test</div>"#);
     }
+
+    #[test]
+    fn it_works_without_importing_helpers() {
+        mod page {
+            use crate::{html, Component, Render};
+            use alloc::string::String;
+
+            pub fn page(attrs: &[(&str, &str)]) -> Component {
+                let active = true;
+                html! {
+                    <div {..attrs} class="a" class:active=active>Test</div>
+                    <p style:width="1px">{%1}</p>
+                }
+            }
+        }
+
+        assert_eq!(
+            page::page(&[("id", "x")]).to_string(),
+            r#"<div id="x" class="a active">Test</div><p style="width: 1px;">1</p>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_classes() {
+        let active = true;
+        let disabled = false;
+        let size: Option<&str> = Some("btn-lg");
+        let result = html! {
+            <button class=classes!["btn btn-primary", ("active", active), ("disabled", disabled), size, "btn"]></button>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<button class="btn btn-primary active btn-lg"></button>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_class_toggles() {
+        let active = true;
+        let disabled = false;
+        let result = html! {
+            <a href="/" class:active=active class="nav-link" class:disabled=disabled>Home</a>
+        }
+        .to_string();
+
+        assert_eq!(result, r#"<a href="/" class="active nav-link">Home</a>"#);
+    }
+
+    #[test]
+    fn it_works_with_styles() {
+        let width = 10;
+        let color = "red";
+        let style = style!["width" => width, "color" => color];
+        let result = html! { <div style=style></div> }.to_string();
+
+        assert_eq!(result, r#"<div style="width: 10; color: red;"></div>"#);
+    }
+
+    #[test]
+    fn it_works_with_style_properties() {
+        let width = "10px";
+        let color = "red;background:url(\"x\")";
+        let result = html! {
+            <div style="display: flex;" style:width=width style:color=color></div>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<div style="display: flex; width: 10px; color: red\3b background:url(\22 x\22 );"></div>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_spread_attrs() {
+        let mut attrs = alloc::collections::BTreeMap::new();
+        attrs.insert("class", Some("active"));
+        attrs.insert("hx-get", Some("/items?a=1&b=2"));
+        attrs.insert("id", None);
+        let result = html! {
+            <a id="link" class="btn" {..&attrs} href="/">Items</a>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<a id="link" class="btn active" hx-get="/items?a=1&amp;b=2" href="/">Items</a>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_boolean_and_invalid_attrs() {
+        let attrs = [
+            ("disabled", true),
+            ("hidden", false),
+            ("onclick=\"alert(1)\"", true),
+        ];
+        let result = html! { <button {..attrs} type="submit"></button> }.to_string();
+
+        assert_eq!(result, r#"<button disabled type="submit"></button>"#);
+    }
+
+    #[test]
+    fn it_works_with_spread_attrs_on_components() {
+        fn Button(attrs: Attrs, elements: Elements) -> Component {
+            html! { <button class="btn" {..attrs}>{elements}</button> }
+        }
+
+        let attrs = Attrs::new()
+            .with("class", "btn-primary")
+            .with("style", "color: red")
+            .with("data-id", 1);
+        let result = html! { <Button {..attrs}>Save</Button> }.to_string();
+
+        assert_eq!(
+            result,
+            r#"<button class="btn btn-primary" style="color: red" data-id="1">Save</button>"#
+        );
+    }
+
+    #[test]
+    fn it_sizes_the_buffer_from_the_template() {
+        let inner = html! { <span>shtml</span> };
+        let component = html! { <div>{inner}</div> };
+
+        assert_eq!(component.html.len(), 29);
+        assert_eq!(component.html.capacity(), 29);
+    }
+
+    #[test]
+    fn it_keeps_temporaries_alive_while_rendering() {
+        let name = "shtml";
+        let mut buffer = String::new();
+        html_into!(&mut buffer, <b>{name.to_uppercase().as_str()}</b>);
+
+        assert_eq!(
+            html! { <p>{name.to_uppercase().as_str()}</p> }.to_string(),
+            "<p>SHTML</p>"
+        );
+        assert_eq!(buffer, "<b>SHTML</b>");
+    }
+
+    #[test]
+    fn it_sizes_the_buffer_from_previous_renders() {
+        // `Fmt` can't know its length up front, so only the size remembered
+        // from the first render stops the second from growing its buffer
+        fn Quote(text: &str) -> Component {
+            html! { <q>{Fmt(text)}</q> }
+        }
+
+        let text = "a".repeat(100);
+        let first = allocations::count(|| drop(Quote(&text)));
+        let second = allocations::count(|| drop(Quote(&text)));
+
+        assert!(first > 1);
+        assert_eq!(second, 1);
+    }
+
+    #[test]
+    fn it_works_with_size_hints() {
+        let items = Vec::from(["a", "bc"]);
+
+        assert_eq!(items.size_hint(), 3);
+        assert_eq!(u8::MAX.size_hint(), "255".len());
+        assert_eq!(i64::MIN.size_hint(), "-9223372036854775808".len());
+        assert_eq!(html! { <p>{items}</p> }.size_hint(), 10);
+    }
+
+    mod allocations {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        std::thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        /// Counts allocations made on the current thread, so tests running
+        /// in parallel don't see each other's.
+        struct Counting;
+
+        unsafe impl GlobalAlloc for Counting {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        #[global_allocator]
+        static COUNTING: Counting = Counting;
+
+        pub fn count(f: impl FnOnce()) -> usize {
+            let before = ALLOCATIONS.with(Cell::get);
+            f();
+            ALLOCATIONS.with(Cell::get) - before
+        }
+    }
+
+    #[test]
+    fn it_renders_into_a_reused_buffer() {
+        let mut buffer = String::with_capacity(64);
+        for (name, count) in [("a", 1), ("b", 22)] {
+            buffer.clear();
+            let allocations = allocations::count(|| {
+                html_into!(&mut buffer, <p class="name">{name}" x"{count}</p>);
+            });
+
+            // hot reloading reads the template's markup from disk on every render
+            if !cfg!(feature = "hot-reload") {
+                assert_eq!(allocations, 0);
+            }
+            assert_eq!(buffer, alloc::format!(r#"<p class="name">{name} x{count}</p>"#));
+        }
+
+        let buffer = &mut String::from("<ul>");
+        html_into!(buffer, <li>{1}</li>);
+        html_into!(buffer, <li>{2}</li>);
+        buffer.push_str("</ul>");
+
+        assert_eq!(buffer, "<ul><li>1</li><li>2</li></ul>");
+
+        let item = html! { <li>{3}</li> };
+        buffer.clear();
+        item.render_into(buffer);
+        item.render_into(buffer);
+
+        assert_eq!(buffer, "<li>3</li><li>3</li>");
+    }
+
+    #[test]
+    fn it_escapes_into_a_writer() {
+        let inputs = [
+            "",
+            "plain text without any special characters",
+            "<>&\"'",
+            "a<b",
+            "twelve bytes<",
+            "émoji 🦀 & <ünïcödé> in 'the' middle of \"a longer string\"",
+        ];
+        for input in inputs {
+            let mut output = String::new();
+            escape_into(input, &mut output).unwrap();
+            let expected = input
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+                .replace('\'', "&#39;");
+
+            assert_eq!(output, expected);
+            assert_eq!(escape(input), expected);
+        }
+    }
+
+    #[test]
+    fn it_keeps_punctuation_in_text() {
+        let result = html! { <p>Hello, world! Meet at 9:30 (roughly).</p> }.to_string();
+
+        assert_eq!(result, r#"<p>Hello, world! Meet at 9:30 (roughly).</p>"#);
+    }
+
+    #[test]
+    fn it_collapses_whitespace_on_a_line() {
+        let a = "a";
+        let b = "b";
+        let result = html! {
+            <p>{a} {b}   and    <b>bold</b> <i>italic</i>, <a href="/">link</a>.</p>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<p>a b and <b>bold</b> <i>italic</i>, <a href="/">link</a>.</p>"#
+        );
+    }
+
+    #[test]
+    fn it_removes_whitespace_with_line_breaks() {
+        let name = "shtml";
+        let result = html! {
+            <ul>
+                <li>
+                    Hello
+                    {name}
+                </li>
+                <li>Hello {name}!</li>
+            </ul>
+        }
+        .to_string();
+
+        assert_eq!(result, r#"<ul><li>Helloshtml</li><li>Hello shtml!</li></ul>"#);
+    }
+
+    #[test]
+    fn it_joins_text_across_lines() {
+        let result = html! {
+            <p>
+                Some long
+                text here, wrapped
+                over three lines. <b>bold</b>
+            </p>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            "<p>Some long text here, wrapped over three lines. <b>bold</b></p>"
+        );
+    }
+
+    #[test]
+    fn it_preserves_whitespace_in_pre() {
+        let result = html! {
+            <pre>
+                "fn main() {"
+                    "println!(\"hi\");"
+                "}"
+            </pre>
+        }
+        .to_string();
+
+        assert_eq!(result, "<pre>fn main() {\n    println!(\"hi\");\n}</pre>");
+    }
+
+    #[test]
+    fn it_pretty_prints() {
+        let component = html! {
+            <!DOCTYPE html>
+            <html lang="en">
+                <head>
+                    <meta charset="utf-8"/>
+                    <title>shtml</title>
+                </head>
+                <body>
+                    <div class="container">
+                        <p>Hello <b>world</b>, <a href="/">home</a></p>
+                        <ul>
+                            <li>1</li>
+                            <li>2</li>
+                        </ul>
+                        <!-- "comment" -->
+                    </div>
+                    <pre>
+                        "keep"
+                            "this"
+                    </pre>
+                </body>
+            </html>
+        };
+
+        assert_eq!(
+            component.pretty().to_string(),
+            r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8"/>
+    <title>shtml</title>
+  </head>
+  <body>
+    <div class="container">
+      <p>Hello <b>world</b>, <a href="/">home</a></p>
+      <ul>
+        <li>1</li>
+        <li>2</li>
+      </ul>
+      <!--comment-->
+    </div>
+    <pre>keep
+    this</pre>
+  </body>
+</html>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_nodes() {
+        let mut nodes = nodes! {
+            <head>
+                <script src="/app.js"></script>
+                <link rel="stylesheet" href="/app.css"/>
+            </head>
+            <body>
+                <h1 class="title">Hello <em>world</em></h1>
+                <!-- "comment" -->
+            </body>
+        };
+
+        assert_eq!(nodes.len(), 2);
+        let body = nodes[1].as_element().unwrap();
+        assert_eq!(body.name, "body");
+        assert_eq!(body.children[0].as_element().unwrap().attr("class"), Some("title"));
+        assert_eq!(body.children[1], Node::Comment("comment".to_string()));
+
+        for node in &mut nodes {
+            node.walk_mut(&mut |node| {
+                if let Some(element) = node.as_element_mut() {
+                    if element.name == "script" {
+                        element.set_attr("nonce", "r4nd0m");
+                    }
+                    for key in ["src", "href"] {
+                        if let Some(url) = element.attr(key) {
+                            let url = alloc::format!("https://cdn.example.com{url}");
+                            element.set_attr(key, &url);
+                        }
+                    }
+                }
+            });
+        }
+
+        assert_eq!(
+            Component::from(nodes).to_string(),
+            r#"<head><script src="https://cdn.example.com/app.js" nonce="r4nd0m"></script><link rel="stylesheet" href="https://cdn.example.com/app.css"/></head><body><h1 class="title">Hello <em>world</em></h1><!--comment--></body>"#
+        );
+    }
+
+    #[test]
+    fn it_builds_the_nodes_html_renders() {
+        fn Card(title: &str, elements: Elements) -> Component {
+            html! { <div class="card"><h2>{title}</h2>{elements}</div> }
+        }
+
+        fn Badge(count: u32) -> Vec<Node> {
+            nodes! { <span class="badge">{count}</span> }
+        }
+
+        let name = "<b>world</b>";
+        let active = true;
+        let extra = Attrs::new().with("data-id", 7);
+        macro_rules! page {
+            ($macro:ident) => {
+                $macro! {
+                    <!DOCTYPE html>
+                    <html>
+                        <head><title>"Hi"</title><style>"p > a {}"</style></head>
+                        <body class="page" class:active=active style:color="red">
+                            <!-- "comment" -->
+                            <h1 {..&extra} hidden>Hello, {name}!</h1>
+                            <Card title="cards">
+                                <p>inside</p>
+                                <Badge count=3/>
+                            </Card>
+                            <pre>
+                                keep
+                                  this
+                            </pre>
+                            <input type="checkbox" checked><br/>
+                            <svg><path d="M0 0"/></svg>
+                            <>{name.to_uppercase().as_str()} <i>"text"</i></>
+                        </body>
+                    </html>
+                }
+            };
+        }
+        let (html, nodes) = (page!(html), page!(nodes));
+
+        assert_eq!(nodes, html.nodes());
+        assert_eq!(Component::from(nodes).to_string(), html.to_string());
+    }
+
+    #[test]
+    fn it_round_trips_nodes() {
+        let x = "<b>\"quoted\" & 'escaped' ünïcödé</b>";
+        let component = html! {
+            <div data-x=x hidden>"ü"{x}<br/><hr><textarea>"<p>raw</p>"</textarea></div>
+            <svg><path d="M0 0"/></svg>
+        };
+
+        assert_eq!(
+            Component::from(component.nodes()).to_string(),
+            component.to_string()
+        );
+    }
+
+    #[test]
+    fn it_selects_elements() {
+        let result = html! {
+            <form id="signup" class="form wide">
+                <label>Email <input type="email" name="email" value="a&b@example.com" /></label>
+                <input type="password" name="password" />
+                <button type="submit">Sign <em>up</em></button>
+            </form>
+        };
+
+        let inputs = result.select("form input");
+        assert_eq!(inputs.len(), 2);
+        let email = result.select("form > label > input[name=email]");
+        assert_eq!(
+            email[0].attr_value("value"),
+            Some("a&b@example.com".to_string())
+        );
+        assert_eq!(result.select("form > input[name=email]").len(), 0);
+        assert_eq!(result.select("#signup.wide > [type^=pass]").len(), 1);
+        assert_eq!(result.select("button, label").len(), 2);
+        assert_eq!(result.select("form")[0].select("em")[0].text(), "up");
+        assert_eq!(result.select("button")[0].text(), "Sign up");
+    }
+
+    #[test]
+    fn it_asserts_html_eq() {
+        let result = html! { <a class="link" href="/?a=1&b=2">Home &amp; away</a> };
+
+        assert_html_eq!(
+            result,
+            "<a  href='/?a=1&amp;b=2' class=\"link\">\n  Home &amp;   away\n</a>"
+        );
+        assert_eq!(testing::unescape("&lt;&#39;&#x41;&amp;&nope"), "<'A&&nope");
+    }
+
+    #[test]
+    #[should_panic(expected = "html is not equal")]
+    fn it_fails_html_eq() {
+        assert_html_eq!(html! { <p>one</p> }, "<p>two</p>");
+    }
+
+    #[cfg(feature = "insta")]
+    #[test]
+    fn it_snapshots_components() {
+        let result = html! {
+            <nav class="menu" id="main">
+                <ul><li><a href="/">Home</a></li><li><a href="/about">About &amp; contact</a></li></ul>
+            </nav>
+        };
+
+        assert_html_snapshot!(result);
+    }
+
+    #[test]
+    fn it_includes_html_files() {
+        fn Card(title: &str, elements: Elements) -> Component {
+            html! { <section><h1>{title}</h1>{elements}</section> }
+        }
+
+        let title = "Welcome";
+        let name = "<shtml>";
+        let result = include_html!("tests/templates/page.html").to_string();
+
+        assert_eq!(
+            result,
+            "<!DOCTYPE html><html><head><title>Welcome</title></head><body class=\"page\"><section><h1>Welcome</h1>Hello, &lt;shtml&gt;!</section><pre>keep\n  this</pre></body></html>"
+        );
+    }
+
+    #[cfg(all(feature = "hot-reload", debug_assertions))]
+    #[test]
+    fn it_reloads_static_markup() {
+        use std::{
+            fs::{self, File},
+            string::String,
+            time::{Duration, SystemTime},
+        };
+
+        let dir = std::env::temp_dir().join(alloc::format!(
+            "shtml-hot-reload-test-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest: &'static str =
+            String::leak(dir.join("manifest").to_string_lossy().into_owned());
+        let compiled: &[&[&str]] = &[&["<p>", "</p>"]];
+        let write = |contents: &str, seconds: u64| {
+            fs::write(manifest, contents).unwrap();
+            let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+            File::options()
+                .write(true)
+                .open(manifest)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+
+        write("42\n2\n4\n<h1>5\n</h1>", 1);
+        let chunks = hot_reload::chunks(manifest, 42, compiled);
+        assert_eq!(chunks.get(0, 0), "<h1>");
+        assert_eq!(chunks.len(0), 9);
+
+        write("7\n2\n4\n<h2>5\n</h2>", 2);
+        let chunks = hot_reload::chunks(manifest, 42, compiled);
+        assert_eq!(chunks.get(0, 0), "<p>");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let result = html! { <p>Hello {"world"}</p> };
+        assert_eq!(result.to_string(), "<p>Hello world</p>");
+    }
+
+    #[test]
+    fn it_derives_render_for_enums() {
+        #[derive(Render)]
+        enum Status {
+            Active,
+            #[render(text = "Banned <forever>")]
+            Banned,
+            #[allow(dead_code)]
+            Pending(u8),
+        }
+
+        let result = html! { <ul><li>{Status::Active}</li><li>{Status::Banned}</li></ul> };
+
+        assert_eq!(
+            result.to_string(),
+            "<ul><li>Active</li><li>Banned &lt;forever&gt;</li></ul>"
+        );
+        assert_eq!(Status::Pending(1).size_hint(), 7);
+    }
+
+    #[test]
+    fn it_derives_render_without_importing_helpers() {
+        mod status {
+            #[derive(crate::Render)]
+            pub enum Status {
+                Active,
+            }
+
+            #[derive(crate::Render)]
+            #[render(template = status_badge)]
+            pub struct Badge(pub Status);
+
+            fn status_badge(badge: &Badge) -> crate::Component {
+                use crate::{html, Component, Render};
+                use alloc::string::String;
+
+                html! { <b>{&badge.0}</b> }
+            }
+        }
+
+        let result = html! { <p>{status::Badge(status::Status::Active)}</p> };
+
+        assert_eq!(result.to_string(), "<p><b>Active</b></p>");
+    }
+
+    #[test]
+    fn it_derives_render_with_a_template() {
+        #[derive(Render)]
+        #[render(template = user_card)]
+        struct User<'a> {
+            name: &'a str,
+            role: &'a str,
+        }
+
+        fn user_card(user: &User) -> Component {
+            html! { <div class="user" data-role=user.role>{user.name}</div> }
+        }
+
+        let users = Vec::from([User {
+            name: "a&b",
+            role: "admin",
+        }]);
+        let result = html! { <main>{users}</main> };
+
+        assert_eq!(
+            result.to_string(),
+            r#"<main><div class="user" data-role="admin">a&amp;b</div></main>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_display_types() {
+        struct Id(u32);
+
+        impl fmt::Display for Id {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "<#{:04}>", self.0)
+            }
+        }
+
+        let id = Id(7);
+        let result = html! { <p id=Fmt(&id)>{Fmt(&id)} {%id} {%'&'}</p> };
+
+        assert_eq!(
+            result.to_string(),
+            r#"<p id="&lt;#0007&gt;">&lt;#0007&gt; &lt;#0007&gt; &amp;</p>"#
+        );
+    }
+
+    #[test]
+    fn it_formats_numbers() {
+        let render = |number: Number| html! { {number} }.to_string();
+
+        assert_eq!(render(Number::from(3.0)), "3");
+        assert_eq!(render(Number::from(1e21)), "1000000000000000000000");
+        assert_eq!(render(Number::from(2.5).decimals(2)), "2.50");
+        assert_eq!(
+            render(Number::from(-1234567.891).decimals(2).separator(',')),
+            "-1,234,567.89"
+        );
+        assert_eq!(
+            render(Number::from(1234.5).separator('.').point(',')),
+            "1.234,5"
+        );
+        assert_eq!(render(Number::from(999.999).decimals(2).separator(',')), "1,000.00");
+        assert_eq!(render(Number::from(-0.001).decimals(2)), "0.00");
+        assert_eq!(render(Number::from(0.125).percent().decimals(1)), "12.5%");
+        assert_eq!(render(Number::from(-5).currency("$")), "-$5.00");
+        assert_eq!(render(Number::from(12u64).currency("<€>").decimals(0)), "&lt;€&gt;12");
+        assert_eq!(render(Number::from(950).compact()), "950");
+        assert_eq!(render(Number::from(1000).compact()), "1k");
+        assert_eq!(render(Number::from(15_300).compact()), "15.3k");
+        assert_eq!(render(Number::from(999_960).compact()), "1M");
+        assert_eq!(render(Number::from(2_500_000_000u64).compact()), "2.5B");
+        assert_eq!(render(Number::from(1234).compact().decimals(2)), "1.23k");
+        assert_eq!(render(Number::from(f64::NAN)), "NaN");
+        assert_eq!(
+            render(Number::from(u64::MAX).separator(',')),
+            "18,446,744,073,709,551,615"
+        );
+        assert_eq!(
+            render(Number::from(9_007_199_254_740_993u64)),
+            "9007199254740993"
+        );
+        assert_eq!(render(Number::from(i64::MIN)), "-9223372036854775808");
+        assert_eq!(render(Number::from(-1234).decimals(2).separator(',')), "-1,234.00");
+        assert_eq!(render(Number::from(5).percent()), "500%");
+    }
+
+    #[cfg(feature = "i18n")]
+    #[test]
+    fn it_translates() {
+        let name = "<shtml>";
+        let inbox = |count: usize| html! { <p>{t!("inbox", count = count)}</p> };
+
+        assert_eq!(
+            html! { <h1>{t!("greeting", name = name)}</h1> }.to_string(),
+            "<h1>Hello, &lt;shtml&gt;!</h1>"
+        );
+        assert_eq!(inbox(0).to_string(), "<p>No messages</p>");
+        assert_eq!(inbox(1).to_string(), "<p>One message</p>");
+        assert_eq!(inbox(5).to_string(), "<p>5 messages</p>");
+        assert_eq!(t!("tag").to_string(), "Use &lt;b&gt; &amp; {braces}");
+        assert_eq!(
+            t!("greeting", name = name.to_uppercase().as_str()).to_string(),
+            "Hello, &lt;SHTML&gt;!"
+        );
+
+        let result = html! {
+            <Provider value=Locale("fr-CA".into())>
+                {t!("greeting", name = name)}
+                {inbox(0)}
+                {inbox(2)}
+                {t!("tag")}
+            </Provider>
+        };
+        assert_eq!(
+            result.to_string(),
+            "Bonjour, &lt;shtml&gt; !<p>0 message</p><p>2 messages</p>Use &lt;b&gt; &amp; {braces}"
+        );
+    }
+
+    #[cfg(feature = "i18n")]
+    #[test]
+    fn it_translates_without_importing_helpers() {
+        mod greeting {
+            pub fn greeting(name: &str) -> crate::Component {
+                crate::t!("greeting", name = name)
+            }
+        }
+
+        assert_eq!(greeting::greeting("a").to_string(), "Hello, a!");
+    }
+
+    #[test]
+    fn it_sanitizes_html() {
+        let sanitizer = Sanitizer::new();
+        let sanitize = |html: &str| sanitizer.sanitize(html).to_string();
+
+        assert_eq!(
+            sanitize(
+                r#"<p class="x" onclick="steal()">Hi <b>there</b><script>steal()</script></p>"#
+            ),
+            "<p>Hi <b>there</b></p>"
+        );
+        assert_eq!(
+            sanitize(
+                r#"<A HREF="https://example.com" target=_blank>link</A><a href="javascript:steal()">x</a>"#
+            ),
+            r#"<a href="https://example.com" rel="noopener noreferrer">link</a><a rel="noopener noreferrer">x</a>"#
+        );
+        assert_eq!(
+            sanitize(
+                r#"<form action="/login"><input name="password"><p>a < b & c &amp; d</p></form>"#
+            ),
+            "<p>a &lt; b &amp; c &amp; d</p>"
+        );
+        assert_eq!(
+            sanitize(r#"<p title='say "hi"'>unclosed <i>tags<!-- comment -->"#),
+            r#"<p title="say &quot;hi&quot;">unclosed <i>tags</i></p>"#
+        );
+        assert_eq!(
+            sanitize(
+                r#"<img src="/cat.png" alt="cat" onerror="steal()"><img src="data:image/png;base64,AA">"#
+            ),
+            r#"<img src="/cat.png" alt="cat"><img>"#
+        );
+    }
+
+    #[test]
+    fn it_sanitizes_with_a_policy() {
+        let sanitizer = Sanitizer::empty()
+            .allow_tags(&["a", "p", "span"])
+            .allow_attrs("a", &["href"])
+            .allow_attrs("*", &["class"])
+            .url_schemes(&["https"]);
+
+        assert_eq!(
+            sanitizer
+                .sanitize(r#"<p class="intro"><span class="x">Hi</span> <b>there</b> <a href="http://x.com">x</a></p>"#)
+                .to_string(),
+            r#"<p class="intro"><span class="x">Hi</span> there <a>x</a></p>"#
+        );
+        assert_eq!(
+            Sanitizer::new()
+                .deny_tags(&["img"])
+                .link_rel(None)
+                .sanitize(r#"<a href="mailto:hi@example.com">mail</a><img src="/x.png">"#)
+                .to_string(),
+            r#"<a href="mailto:hi@example.com">mail</a>"#
+        );
+        assert_eq!(
+            Sanitizer::empty()
+                .sanitize("<h1>Title</h1><p>Body</p>")
+                .to_string(),
+            "TitleBody"
+        );
+    }
+
+    #[test]
+    fn it_sanitizes_xss_payloads() {
+        const PAYLOADS: &[&str] = &[
+            "<script>alert(1)</script>",
+            "<SCRIPT SRC=//evil.com/xss.js></SCRIPT>",
+            "<scr<script>ipt>alert(1)</script>",
+            "<<script>alert(1);//<</script>",
+            "<img src=x onerror=alert(1)>",
+            "<img src=x onerror=alert(1)//>",
+            "<IMG SRC=\"javascript:alert('XSS');\">",
+            "<IMG SRC=JaVaScRiPt:alert('XSS')>",
+            "<img src=\"jav\tascript:alert(1)\">",
+            "<img src=\"jav&#x09;ascript:alert(1)\">",
+            "<img src=\" &#14;  javascript:alert(1)\">",
+            "<a href=\"&#106;&#97;&#118;&#97;&#115;&#99;&#114;&#105;&#112;&#116;&#58;alert(1)\">x</a>",
+            "<a href=\"&#x6A&#x61&#x76&#x61&#x73&#x63&#x72&#x69&#x70&#x74&#x3A;alert(1)\">x</a>",
+            "<a href=\"&#0000106&#0000097&#0000118&#0000097&#0000115&#0000099&#0000114&#0000105&#0000112&#0000116&#0000058alert(1)\">x</a>",
+            "<a href=\"javascript&colon;alert(1)\">x</a>",
+            "<a href=\"java&Tab;script:alert(1)\">x</a>",
+            "<a href=\"vbscript:msgbox(1)\">x</a>",
+            "<a href=\"data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==\">x</a>",
+            "<a href='x\" onclick=\"alert(1)'>x</a>",
+            "<p title=\"</p><script>alert(1)</script>\">x</p>",
+            "<svg onload=alert(1)>",
+            "<svg><script>alert(1)</script></svg>",
+            "<math><mtext><table><mglyph><style><img src=x onerror=alert(1)>",
+            "<iframe src=\"javascript:alert(1)\"></iframe>",
+            "<iframe srcdoc=\"<script>alert(1)</script>\"></iframe>",
+            "<object data=\"javascript:alert(1)\"></object>",
+            "<embed src=\"javascript:alert(1)\">",
+            "<body onload=alert(1)>",
+            "<div style=\"background:url(javascript:alert(1))\">x</div>",
+            "<div style=\"width: expression(alert(1))\">x</div>",
+            "<form action=\"javascript:alert(1)\"><button>x</button></form>",
+            "<button formaction=\"javascript:alert(1)\">x</button>",
+            "<input onfocus=alert(1) autofocus>",
+            "<details open ontoggle=alert(1)>",
+            "<video><source onerror=\"alert(1)\"></video>",
+            "<meta http-equiv=\"refresh\" content=\"0;url=javascript:alert(1)\">",
+            "<base href=\"javascript:alert(1)//\">",
+            "<link rel=stylesheet href=\"javascript:alert(1)\">",
+            "<style>@import 'javascript:alert(1)';</style>",
+            "<noscript><p title=\"</noscript><img src=x onerror=alert(1)>\"></noscript>",
+            "<textarea></textarea><img src=x onerror=alert(1)></textarea>",
+            "<title><img src=x onerror=alert(1)></title>",
+            "<template><img src=x onerror=alert(1)></template>",
+            "<!--<img src=x onerror=alert(1)>-->",
+            "<!--><img src=x onerror=alert(1)>-->",
+            "<![CDATA[<img src=x onerror=alert(1)>]]>",
+            "<b onmouseover=alert(1)>x</b>",
+            "<a href=\"  JAVASCRIPT:alert(1)\">x</a>",
+            "<a href=\"javascript\u{0}:alert(1)\">x</a>",
+            "<a href=\"/ok\" onclick=\"alert(1)\" HREF=\"javascript:alert(1)\">x</a>",
+            "<img src=`javascript:alert(1)`>",
+            "<img \"\"\"><script>alert(1)</script>\">",
+            "<a href=\"#\" =\"onclick=alert(1)\">x</a>",
+            "<xmp><img src=x onerror=alert(1)></xmp>",
+            "<plaintext><img src=x onerror=alert(1)>",
+        ];
+
+        let sanitizer = Sanitizer::new();
+        for payload in PAYLOADS {
+            let clean = sanitizer.sanitize(payload).to_string();
+            assert_eq!(
+                sanitizer.sanitize(&clean).to_string(),
+                clean,
+                "{payload} isn't stable"
+            );
+            for node in parse(&clean) {
+                node.walk(&mut |node| {
+                    let Some(element) = node.as_element() else {
+                        assert!(!matches!(node, Node::Comment(_)), "{payload} -> {clean}");
+                        return;
+                    };
+                    assert!(
+                        ["a", "b", "div", "i", "img", "p"].contains(&element.name.as_str()),
+                        "{payload} -> {clean}"
+                    );
+                    for (key, value) in &element.attrs {
+                        let value = value.as_deref().unwrap_or("");
+                        assert!(
+                            ["href", "src", "title", "alt", "rel"].contains(&key.as_str()),
+                            "{payload} -> {clean}"
+                        );
+                        assert!(
+                            !value.to_ascii_lowercase().contains("script:"),
+                            "{payload} -> {clean}"
+                        );
+                    }
+                });
+            }
+        }
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn it_renders_markdown() {
+        let source = "# Getting *started*\n\nSome `code` and <b>html</b>\n\n## Getting started\n";
+        let result = html! { <article><Markdown source=source /></article> }.to_string();
+
+        assert_eq!(
+            result,
+            "<article><h1 id=\"getting-started\">Getting <em>started</em></h1>\n\
+             <p>Some <code>code</code> and &lt;b&gt;html&lt;/b&gt;</p>\n\
+             <h2 id=\"getting-started-1\">Getting started</h2>\n</article>"
+        );
+        assert_eq!(
+            html! { {Markdown(source).allow_html()} }.to_string(),
+            "<h1 id=\"getting-started\">Getting <em>started</em></h1>\n\
+             <p>Some <code>code</code> and <b>html</b></p>\n\
+             <h2 id=\"getting-started-1\">Getting started</h2>\n"
+        );
+
+        let nodes = nodes! { <article><Markdown source=source /></article> };
+        let heading = nodes[0].as_element().unwrap().children[0].as_element();
+        assert_eq!(heading.unwrap().attr("id"), Some("getting-started"));
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn it_drops_unsafe_markdown_urls() {
+        let source = "[a](javascript:alert(1)) [b](JavaScript&#58;alert(1)) [c](/ok) \
+                      ![d](data:image/png;base64,AA) ![e](data:text/html,x)\n\n\
+                      <script>alert(1)</script>\n";
+
+        assert_eq!(
+            html! { {Markdown(source)} }.to_string(),
+            "<p><a href=\"\">a</a> <a href=\"\">b</a> <a href=\"/ok\">c</a> \
+             <img src=\"data:image/png;base64,AA\" alt=\"d\" /> <img src=\"\" alt=\"e\" /></p>\n\
+             &lt;script&gt;alert(1)&lt;/script&gt;\n"
+        );
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn it_replaces_markdown_elements() {
+        fn Code(language: &str, code: &str) -> Component {
+            html! { <pre data-language=language><code>{code}</code></pre> }
+        }
+
+        let source =
+            "## Install\n\n```sh\ncargo add <shtml>\n```\n\nSee [the docs](https://docs.rs).\n";
+        let markdown = Markdown(source)
+            .code_block(|language, code| html! { <Code language=language code=code /> })
+            .heading(|level, id, content| {
+                let href = alloc::format!("#{}", id);
+                html! { <div class="heading" data-level=level><a href=href>{content}</a></div> }
+            })
+            .link(|href, content| html! { <a href=href target="_blank">{content}</a> });
+
+        assert_eq!(
+            html! { {markdown} }.to_string(),
+            "<div class=\"heading\" data-level=\"2\"><a href=\"#install\">Install</a></div>\
+             <pre data-language=\"sh\"><code>cargo add &lt;shtml&gt;\n</code></pre>\n\
+             <p>See <a href=\"https://docs.rs\" target=\"_blank\">the docs</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn it_renders_svg_and_mathml() {
+        fn Label(elements: Elements) -> Component {
+            html! { <span class="label">{elements}</span> }
+        }
+
+        let color = "red";
+        let result = html! {
+            <svg viewBox="0 0 10 10" preserveAspectRatio="none">
+                <defs>
+                    <linearGradient id="fade" gradientTransform="rotate(90)">
+                        <stop offset="0" stop-color=color></stop>
+                    </linearGradient>
+                </defs>
+                <path d="M0 0L10 10"></path>
+                <Label />
+                <font-face font-family="x"></font-face>
+                <foreignObject width="10" height="10">
+                    <Label>hi</Label>
+                    <div></div>
+                </foreignObject>
+            </svg>
+            <math><mi>x</mi><mspace width="1em"></mspace></math>
+            <div></div>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<svg viewBox="0 0 10 10" preserveAspectRatio="none"><defs><linearGradient id="fade" gradientTransform="rotate(90)"><stop offset="0" stop-color="red"/></linearGradient></defs><path d="M0 0L10 10"/><Label/><font-face font-family="x"/><foreignObject width="10" height="10"><span class="label">hi</span><div></div></foreignObject></svg><math><mi>x</mi><mspace width="1em"/></math><div></div>"#
+        );
+    }
+
+    #[test]
+    fn it_renders_xml() {
+        struct Post {
+            title: &'static str,
+            url: &'static str,
+        }
+
+        fn Item(post: &Post) -> Component {
+            xml! {
+                <item>
+                    <title>{post.title}</title>
+                    <link>{post.url}</link>
+                    <guid isPermaLink="true">{post.url}</guid>
+                    <category></category>
+                </item>
+            }
+        }
+
+        let posts = [Post {
+            title: "Tom & Jerry\u{0}'s \u{1b}<feed>",
+            url: "https://example.com/tom?a=1&b=2",
+        }];
+        let result = xml! {
+            <?xml version="1.0" encoding="UTF-8"?>
+            <?xml-stylesheet href="/feed.xsl" type="text/xsl"?>
+            <rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+                <channel>
+                    <atom:link href="https://example.com/feed.xml" rel="self"></atom:link>
+                    <Title>Posts</Title>
+                    {posts.iter().map(Item).collect::<Vec<_>>()}
+                </channel>
+            </rss>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<?xml version="1.0" encoding="UTF-8"?><?xml-stylesheet href="/feed.xsl" type="text/xsl"?><rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"><channel><atom:link href="https://example.com/feed.xml" rel="self"/><Title>Posts</Title><item><title>Tom &amp; Jerry&#39;s &lt;feed&gt;</title><link>https://example.com/tom?a=1&amp;b=2</link><guid isPermaLink="true">https://example.com/tom?a=1&amp;b=2</guid><category/></item></channel></rss>"#
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_provides_context_without_importing_provider() {
+        mod page {
+            use crate::{html, Component, Nonce, Render};
+            use alloc::string::String;
+
+            pub fn page() -> Component {
+                html! { <Provider value=Nonce("n".into())><style></style></Provider> }
+            }
+        }
+
+        assert_eq!(page::page().to_string(), r#"<style nonce="n"></style>"#);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_renders_xml_without_html_checks() {
+        let result = html! {
+            <Provider value=Nonce("r4nd0m".into())>
+                {xml! { <feed><entry online="yes"/><script></script><style/></feed> }}
+            </Provider>
+        };
+
+        assert_eq!(
+            result.to_string(),
+            r#"<feed><entry online="yes"/><script/><style/></feed>"#
+        );
+    }
+
+    #[cfg(not(feature = "strict-csp"))]
+    #[test]
+    fn it_renders_unsafe_attributes() {
+        let attrs = Vec::from([("id".to_string(), "save".to_string())]);
+        let result = html! {
+            <button unsafe:onclick="save()">Save</button>
+            <a unsafe:href="javascript:save()" {..attrs}>Save</a>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<button onclick="save()">Save</button><a href="javascript:save()" id="save">Save</a>"#
+        );
+    }
+
+    #[test]
+    fn it_works_with_non_ascii_attribute_names() {
+        let result = html! { <div 日本="x" o日="y" 日n="z"></div> };
+
+        assert_eq!(result.to_string(), r#"<div 日本="x" o日="y" 日n="z"></div>"#);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_adds_csp_nonces() {
+        fn Head() -> Component {
+            let src: &[(&str, &str)] = &[("src", "/spread.js")];
+            let own: &[(&str, &str)] = &[("nonce", "spread")];
+            html! {
+                <style>"body { margin: 0 }"</style>
+                <script src="/app.js"></script>
+                <script nonce="own">run()</script>
+                <script {..src}></script>
+                <script {..own}></script>
+                <link rel="stylesheet" href="/app.css" />
+            }
+        }
+
+        assert_eq!(
+            html! { <Head /> }.to_string(),
+            r#"<style>body { margin: 0 }</style><script src="/app.js"></script><script nonce="own">run()</script><script src="/spread.js"></script><script nonce="spread"></script><link rel="stylesheet" href="/app.css"/>"#
+        );
+        assert_eq!(
+            html! {
+                <Provider value=Nonce("r4nd\"0m".into())>
+                    <Head />
+                </Provider>
+            }
+            .to_string(),
+            r#"<style nonce="r4nd&quot;0m">body { margin: 0 }</style><script nonce="r4nd&quot;0m" src="/app.js"></script><script nonce="own">run()</script><script nonce="r4nd&quot;0m" src="/spread.js"></script><script nonce="spread"></script><link rel="stylesheet" href="/app.css"/>"#
+        );
+
+        let src: &[(&str, &str)] = &[("src", "/spread.js")];
+        let nodes = nodes! {
+            <Provider value=Nonce("r4nd0m".into())>
+                <script src="/app.js"></script>
+                <script {..src}></script>
+            </Provider>
+        };
+        let nonces = nodes
+            .iter()
+            .map(|node| node.as_element().unwrap().attr("nonce"));
+        assert_eq!(nonces.collect::<Vec<_>>(), [Some("r4nd0m"); 2]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_works_with_context() {
+        struct Theme(&'static str);
+
+        fn Button(elements: Elements) -> Component {
+            let class = use_context::<Theme>().map_or("light", |theme| theme.0);
+            html! { <button class=class>{elements}</button> }
+        }
+
+        fn Toolbar(elements: Elements) -> Component {
+            html! { <nav>{elements}</nav> }
+        }
+
+        let result = html! {
+            <Provider value=Theme("dark")>
+                <Toolbar>
+                    <Button>Save</Button>
+                </Toolbar>
+            </Provider>
+            <Button>Cancel</Button>
+        }
+        .to_string();
+
+        assert_eq!(
+            result,
+            r#"<nav><button class="dark">Save</button></nav><button class="light">Cancel</button>"#
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_works_with_nested_context() {
+        struct Locale(&'static str);
+
+        fn Greeting() -> Component {
+            let locale = use_context::<Locale>().map_or("none", |locale| locale.0);
+            html! { <p>{locale}</p> }
+        }
+
+        let result = html! {
+            <Provider value=Locale("en")>
+                <Greeting/>
+                <Provider value=Locale("fr")>
+                    <Greeting/>
+                </Provider>
+                <Greeting/>
+            </Provider>
+        }
+        .to_string();
+
+        assert_eq!(result, r#"<p>en</p><p>fr</p><p>en</p>"#);
+    }
 }
 
 pub type Elements = Component;
@@ -359,59 +1687,99 @@ pub struct Component {
 
 pub trait Render {
     fn render_to_string(&self, buffer: &mut String);
+
+    /// An estimate of how many bytes `render_to_string` will push, used by
+    /// `html!` to size its buffer up front.
+    fn size_hint(&self) -> usize {
+        0
+    }
+
+    /// Adds what `render_to_string` renders to `nodes`, which is how `nodes!`
+    /// renders `{expr}` and components. It's treated as text unless a type
+    /// that renders markup says otherwise, like `Component`, whose html is
+    /// parsed since it's all a component returns.
+    fn render_nodes(&self, nodes: &mut Vec<Node>) {
+        let mut text = String::new();
+        self.render_to_string(&mut text);
+        node::push_text(nodes, &text);
+    }
 }
 
 macro_rules! impl_render_int {
-    ($t:ty) => {
+    ($t:ty, $max_width:expr) => {
         impl Render for $t {
             fn render_to_string(&self, buffer: &mut String) {
                 let mut b = itoa::Buffer::new();
                 buffer.push_str(b.format(*self));
             }
+
+            fn size_hint(&self) -> usize {
+                $max_width
+            }
         }
     };
 }
 
 macro_rules! impl_render_float {
-    ($t:ty) => {
+    ($t:ty, $max_width:expr) => {
         impl Render for $t {
             fn render_to_string(&self, buffer: &mut String) {
                 let mut b = ryu::Buffer::new();
                 buffer.push_str(b.format(*self));
             }
+
+            fn size_hint(&self) -> usize {
+                $max_width
+            }
         }
     };
 }
 
-impl_render_int!(u8);
-impl_render_int!(i8);
-impl_render_int!(u16);
-impl_render_int!(i16);
-impl_render_int!(i64);
-impl_render_int!(u64);
-impl_render_int!(i32);
-impl_render_int!(u32);
-impl_render_int!(usize);
-impl_render_int!(isize);
+impl_render_int!(u8, 3);
+impl_render_int!(i8, 4);
+impl_render_int!(u16, 5);
+impl_render_int!(i16, 6);
+impl_render_int!(i64, 20);
+impl_render_int!(u64, 20);
+impl_render_int!(i32, 11);
+impl_render_int!(u32, 10);
+impl_render_int!(usize, 20);
+impl_render_int!(isize, 20);
 
-impl_render_float!(f64);
-impl_render_float!(f32);
+impl_render_float!(f64, 24);
+impl_render_float!(f32, 16);
 
 impl Render for Component {
     fn render_to_string(&self, buffer: &mut String) {
         buffer.push_str(&self.html);
     }
+
+    fn size_hint(&self) -> usize {
+        self.html.len()
+    }
+
+    fn render_nodes(&self, nodes: &mut Vec<Node>) {
+        nodes.extend(parse(&self.html));
+    }
 }
 
 impl Render for String {
     fn render_to_string(&self, buffer: &mut String) {
-        buffer.push_str(&escape(self))
+        let _ = escape_into(self, buffer);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.len()
     }
 }
 
 impl Render for &str {
     fn render_to_string(&self, buffer: &mut String) {
-        buffer.push_str(&escape(*self))
+        let _ = escape_into(self, buffer);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.len()
     }
 }
 
@@ -422,21 +1790,44 @@ where
     fn render_to_string(&self, buffer: &mut String) {
         self.iter().for_each(|s| s.render_to_string(buffer));
     }
+
+    fn size_hint(&self) -> usize {
+        self.iter().map(Render::size_hint).sum()
+    }
+
+    fn render_nodes(&self, nodes: &mut Vec<Node>) {
+        self.iter().for_each(|s| s.render_nodes(nodes));
+    }
 }
 
-impl <T> Render for Vec<(T, T)>
-where
-    T: Render,
-{
+/// Renders anything that implements `Display`, escaping the text as it's
+/// formatted instead of going through `to_string`. In `html!`, `{%expr}` is
+/// short for `{Fmt(&expr)}`.
+#[derive(Debug, Clone, Copy)]
+pub struct Fmt<T>(pub T);
+
+impl<T: fmt::Display> Render for Fmt<T> {
     fn render_to_string(&self, buffer: &mut String) {
-        self.iter().for_each(|(key, value)| {
-            buffer.push_str(" ");
-            key.render_to_string(buffer);
-            buffer.push_str("=");
-            buffer.push_str(r#"""#);
-            value.render_to_string(buffer);
-            buffer.push_str(r#"""#);
-        });
+        let _ = fmt::write(&mut Escaper(buffer), format_args!("{}", self.0));
+    }
+}
+
+/// A writer that escapes everything written through it.
+struct Escaper<'a, W: ?Sized>(&'a mut W);
+
+impl<W: fmt::Write + ?Sized> fmt::Write for Escaper<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        escape_into(s, self.0)
+    }
+}
+
+impl Component {
+    /// Appends the html to `buffer`, for when a `Component` was already built,
+    /// like one returned from a component function. Use `html_into!` to render
+    /// a template straight into the buffer without building one.
+    pub fn render_into(&self, buffer: &mut String) {
+        buffer.reserve(self.size_hint());
+        self.render_to_string(buffer);
     }
 }
 
@@ -448,28 +1839,68 @@ impl fmt::Display for Component {
 
 pub fn escape<'a, S: Into<Cow<'a, str>>>(input: S) -> Cow<'a, str> {
     let input = input.into();
-    fn needs_escaping(c: char) -> bool {
-        c == '<' || c == '>' || c == '&' || c == '"' || c == '\''
-    }
-
-    if let Some(first) = input.find(needs_escaping) {
-        let mut output = String::from(&input[0..first]);
-        output.reserve(input.len() - first);
-        let rest = input[first..].chars();
-        for c in rest {
-            match c {
-                '<' => output.push_str("&lt;"),
-                '>' => output.push_str("&gt;"),
-                '&' => output.push_str("&amp;"),
-                '"' => output.push_str("&quot;"),
-                '\'' => output.push_str("&#39;"),
-                _ => output.push(c),
-            }
+    match find_escape(input.as_bytes(), 0) {
+        Some(first) => {
+            let mut output = String::from(&input[0..first]);
+            output.reserve(input.len() - first);
+            let _ = escape_into(&input[first..], &mut output);
+            Cow::Owned(output)
         }
-        Cow::Owned(output)
-    } else {
-        input
+        None => input,
+    }
+}
+
+/// Escapes `input` straight into `output`, writing runs of bytes that don't
+/// need escaping with a single `write_str`.
+pub fn escape_into<W: fmt::Write + ?Sized>(input: &str, output: &mut W) -> fmt::Result {
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    while let Some(i) = find_escape(bytes, start) {
+        output.write_str(&input[start..i])?;
+        output.write_str(match bytes[i] {
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'&' => "&amp;",
+            b'"' => "&quot;",
+            _ => "&#39;",
+        })?;
+        start = i + 1;
     }
+    output.write_str(&input[start..])
+}
+
+/// Finds the next byte that needs escaping, eight bytes at a time.
+fn find_escape(bytes: &[u8], start: usize) -> Option<usize> {
+    const ONES: u64 = u64::from_ne_bytes([0x01; 8]);
+    const HIGHS: u64 = u64::from_ne_bytes([0x80; 8]);
+
+    fn matches(word: u64, byte: u8) -> u64 {
+        let x = word ^ (ONES * byte as u64);
+        x.wrapping_sub(ONES) & !x & HIGHS
+    }
+
+    fn needs_escaping(byte: &u8) -> bool {
+        matches!(byte, b'<' | b'>' | b'&' | b'"' | b'\'')
+    }
+
+    let mut i = start;
+    while let Some(chunk) = bytes.get(i..i + 8) {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        let found = matches(word, b'<')
+            | matches(word, b'>')
+            | matches(word, b'&')
+            | matches(word, b'"')
+            | matches(word, b'\'');
+        if found != 0 {
+            return Some(i + found.trailing_zeros() as usize / 8);
+        }
+        i += 8;
+    }
+
+    bytes[i..]
+        .iter()
+        .position(needs_escaping)
+        .map(|position| i + position)
 }
 
 #[cfg(feature = "chaos")]
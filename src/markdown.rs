@@ -0,0 +1,260 @@
+use crate::{parse, Component, Node, Render};
+use alloc::{boxed::Box, collections::BTreeSet, string::String, vec::Vec};
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag};
+
+/// Url schemes that run code or read local files, dropped from links and
+/// images unless raw html is allowed.
+const UNSAFE_SCHEMES: [&str; 4] = ["javascript:", "vbscript:", "file:", "data:"];
+
+/// Images that are fine to inline as `data:` urls.
+const SAFE_DATA_IMAGES: [&str; 4] = [
+    "data:image/png",
+    "data:image/gif",
+    "data:image/jpeg",
+    "data:image/webp",
+];
+
+type CodeBlockFn<'a> = dyn Fn(&str, &str) -> Component + 'a;
+type HeadingFn<'a> = dyn Fn(u8, &str, Component) -> Component + 'a;
+type LinkFn<'a> = dyn Fn(&str, Component) -> Component + 'a;
+
+/// CommonMark rendered to html, with an `id` on every heading.
+///
+/// Raw html in the source is escaped and `javascript:` style urls are dropped
+/// unless `allow_html` is called, so it's safe for content people write.
+pub struct Markdown<'a> {
+    source: &'a str,
+    allow_html: bool,
+    code_block: Option<Box<CodeBlockFn<'a>>>,
+    heading: Option<Box<HeadingFn<'a>>>,
+    link: Option<Box<LinkFn<'a>>>,
+}
+
+pub fn Markdown(source: &str) -> Markdown<'_> {
+    Markdown {
+        source,
+        allow_html: false,
+        code_block: None,
+        heading: None,
+        link: None,
+    }
+}
+
+impl<'a> Markdown<'a> {
+    /// Passes raw html and every url through as is, for trusted sources only.
+    pub fn allow_html(mut self) -> Self {
+        self.allow_html = true;
+        self
+    }
+
+    /// Renders code blocks with `f`, called with the fence's language, empty
+    /// for none, and the code.
+    pub fn code_block(mut self, f: impl Fn(&str, &str) -> Component + 'a) -> Self {
+        self.code_block = Some(Box::new(f));
+        self
+    }
+
+    /// Renders headings with `f`, called with the level, the generated id and
+    /// the rendered content.
+    pub fn heading(mut self, f: impl Fn(u8, &str, Component) -> Component + 'a) -> Self {
+        self.heading = Some(Box::new(f));
+        self
+    }
+
+    /// Renders links with `f`, called with the url and the rendered content.
+    pub fn link(mut self, f: impl Fn(&str, Component) -> Component + 'a) -> Self {
+        self.link = Some(Box::new(f));
+        self
+    }
+
+    /// Turns the events of a finished heading, code block or link into its
+    /// replacement.
+    fn replace(&self, tag: Tag<'a>, events: Vec<Event<'a>>, ids: &mut Ids) -> Vec<Event<'a>> {
+        match tag {
+            Tag::Heading {
+                level, id: None, ..
+            } => {
+                let id = ids.unique(&events);
+                match &self.heading {
+                    Some(heading) => {
+                        let content = to_component(events);
+                        let html = heading(level as u8, &id, content).html;
+                        alloc::vec![Event::Html(html.into())]
+                    }
+                    None => wrap(
+                        Tag::Heading {
+                            level,
+                            id: Some(id.into()),
+                            classes: Vec::new(),
+                            attrs: Vec::new(),
+                        },
+                        events,
+                    ),
+                }
+            }
+            Tag::CodeBlock(kind) => match &self.code_block {
+                Some(code_block) => {
+                    let language = match &kind {
+                        CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or(""),
+                        CodeBlockKind::Indented => "",
+                    };
+                    let code = events
+                        .iter()
+                        .filter_map(|event| match event {
+                            Event::Text(text) => Some(&**text),
+                            _ => None,
+                        })
+                        .collect::<String>();
+                    alloc::vec![Event::Html(code_block(language, &code).html.into())]
+                }
+                None => wrap(Tag::CodeBlock(kind), events),
+            },
+            Tag::Link { dest_url, .. } if self.link.is_some() => {
+                let link = self.link.as_ref().unwrap();
+                let html = link(&dest_url, to_component(events)).html;
+                alloc::vec![Event::InlineHtml(html.into())]
+            }
+            tag => wrap(tag, events),
+        }
+    }
+
+    /// Whether events inside `tag` are held back until it ends, so it can be
+    /// given an id or replaced.
+    fn holds(&self, tag: &Tag) -> bool {
+        match tag {
+            Tag::Heading { id, .. } => id.is_none(),
+            Tag::CodeBlock(_) => self.code_block.is_some(),
+            Tag::Link { .. } => self.link.is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl Render for Markdown<'_> {
+    fn render_to_string(&self, buffer: &mut String) {
+        let mut ids = Ids::default();
+        let mut stack: Vec<(Tag, Vec<Event>)> = Vec::new();
+        let mut events = Vec::new();
+        for event in Parser::new_ext(self.source, Options::empty()) {
+            let event = match event {
+                Event::Html(html) | Event::InlineHtml(html) if !self.allow_html => {
+                    Event::Text(html)
+                }
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }) if !self.allow_html && !is_safe_url(&dest_url, false) => {
+                    Event::Start(Tag::Link {
+                        link_type,
+                        dest_url: CowStr::Borrowed(""),
+                        title,
+                        id,
+                    })
+                }
+                Event::Start(Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }) if !self.allow_html && !is_safe_url(&dest_url, true) => {
+                    Event::Start(Tag::Image {
+                        link_type,
+                        dest_url: CowStr::Borrowed(""),
+                        title,
+                        id,
+                    })
+                }
+                event => event,
+            };
+
+            match event {
+                Event::Start(tag) if self.holds(&tag) => {
+                    stack.push((tag, core::mem::take(&mut events)));
+                }
+                Event::End(end) if stack.last().is_some_and(|(tag, _)| tag.to_end() == end) => {
+                    let (tag, parent) = stack.pop().unwrap();
+                    let inner = core::mem::replace(&mut events, parent);
+                    events.extend(self.replace(tag, inner, &mut ids));
+                }
+                event => events.push(event),
+            }
+        }
+
+        html::push_html(buffer, events.into_iter());
+    }
+
+    fn size_hint(&self) -> usize {
+        self.source.len() + self.source.len() / 2
+    }
+
+    fn render_nodes(&self, nodes: &mut Vec<Node>) {
+        let mut html = String::new();
+        self.render_to_string(&mut html);
+        nodes.extend(parse(&html));
+    }
+}
+
+fn wrap<'a>(tag: Tag<'a>, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let end = Event::End(tag.to_end());
+    core::iter::once(Event::Start(tag))
+        .chain(events)
+        .chain(core::iter::once(end))
+        .collect()
+}
+
+fn to_component(events: Vec<Event>) -> Component {
+    let mut html = String::new();
+    html::push_html(&mut html, events.into_iter());
+    Component { html }
+}
+
+fn is_safe_url(url: &str, image: bool) -> bool {
+    let url = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let safe_data = image && SAFE_DATA_IMAGES.iter().any(|data| url.starts_with(data));
+
+    safe_data || !UNSAFE_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+}
+
+/// The heading ids used so far in a document.
+#[derive(Default)]
+struct Ids(BTreeSet<String>);
+
+impl Ids {
+    /// Slugs the heading's text, like `Getting started!` to `getting-started`,
+    /// adding `-1`, `-2` and so on when a heading with that id came before.
+    fn unique(&mut self, events: &[Event]) -> String {
+        let mut slug = String::new();
+        for event in events {
+            if let Event::Text(text) | Event::Code(text) = event {
+                for c in text.chars() {
+                    if c.is_alphanumeric() || c == '_' {
+                        slug.extend(c.to_lowercase());
+                    } else if (c.is_whitespace() || c == '-') && !slug.ends_with('-') {
+                        slug.push('-');
+                    }
+                }
+            }
+        }
+        let slug = match slug.trim_matches('-') {
+            "" => "section",
+            slug => slug,
+        };
+
+        let mut id = String::from(slug);
+        let mut n = 0;
+        while self.0.contains(&id) {
+            n += 1;
+            id = alloc::format!("{}-{}", slug, n);
+        }
+        self.0.insert(id.clone());
+
+        id
+    }
+}
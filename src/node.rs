@@ -0,0 +1,375 @@
+use crate::{escape, Component, Render};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+/// Elements that never have children or a closing tag.
+pub(crate) const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is kept as a single text node instead of being parsed.
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// A node of html built by `nodes!` or parsed by `parse`, that can be inspected
+/// and changed before being rendered. Text and attribute values are stored
+/// escaped, exactly as they appear in the html.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Doctype(String),
+    Comment(String),
+    Text(String),
+    Element(Element),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, Option<String>)>,
+    pub children: Vec<Node>,
+    /// Whether the tag ended in `/>`, kept so `<br/>` and `<path/>` render
+    /// the way they were written.
+    pub self_closing: bool,
+}
+
+impl Element {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+            self_closing: false,
+        }
+    }
+
+    /// Returns the escaped value of `key`, or `Some("")` for a boolean attribute.
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_deref().unwrap_or(""))
+    }
+
+    /// Escapes `value` and sets `key` to it, replacing any existing value.
+    pub fn set_attr(&mut self, key: &str, value: &str) {
+        let value = Some(escape(value).into_owned());
+        match self.attrs.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some((_, existing)) => *existing = value,
+            None => self.attrs.push((key.to_owned(), value)),
+        }
+    }
+
+    pub fn remove_attr(&mut self, key: &str) {
+        self.attrs.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+    }
+
+    pub fn is_void(&self) -> bool {
+        VOID_ELEMENTS
+            .iter()
+            .any(|name| self.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether this renders as a lone `<tag/>`, with no children or closing tag.
+    fn is_empty_tag(&self) -> bool {
+        self.self_closing && self.children.is_empty()
+    }
+
+    pub(crate) fn render_open_tag(&self, buffer: &mut String) {
+        buffer.push('<');
+        buffer.push_str(&self.name);
+        for (key, value) in &self.attrs {
+            buffer.push(' ');
+            buffer.push_str(key);
+            if let Some(value) = value {
+                buffer.push_str("=\"");
+                buffer.push_str(value);
+                buffer.push('"');
+            }
+        }
+        buffer.push_str(if self.is_empty_tag() { "/>" } else { ">" });
+    }
+
+    pub(crate) fn render_close_tag(&self, buffer: &mut String) {
+        buffer.push_str("</");
+        buffer.push_str(&self.name);
+        buffer.push('>');
+    }
+}
+
+impl Node {
+    /// A text node, escaping `text`.
+    pub fn text(text: &str) -> Self {
+        Node::Text(escape(text).into_owned())
+    }
+
+    pub fn as_element(&self) -> Option<&Element> {
+        match self {
+            Node::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    pub fn as_element_mut(&mut self) -> Option<&mut Element> {
+        match self {
+            Node::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` with this node and then every node below it, depth first.
+    pub fn walk(&self, f: &mut impl FnMut(&Node)) {
+        f(self);
+        if let Node::Element(element) = self {
+            element.children.iter().for_each(|child| child.walk(f));
+        }
+    }
+
+    /// Like `walk` but lets `f` change the nodes, including their children.
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Node)) {
+        f(self);
+        if let Node::Element(element) = self {
+            element
+                .children
+                .iter_mut()
+                .for_each(|child| child.walk_mut(f));
+        }
+    }
+}
+
+/// Adds escaped `text` to `nodes`, joining it to the text node before it so
+/// `nodes!` gives the same text nodes as parsing its html would.
+#[doc(hidden)]
+pub fn push_text(nodes: &mut Vec<Node>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match nodes.last_mut() {
+        Some(Node::Text(last)) => last.push_str(text),
+        _ => nodes.push(Node::Text(text.to_owned())),
+    }
+}
+
+impl Render for Node {
+    fn render_to_string(&self, buffer: &mut String) {
+        match self {
+            Node::Doctype(doctype) => {
+                buffer.push_str("<!");
+                buffer.push_str(doctype);
+                buffer.push('>');
+            }
+            Node::Comment(comment) => {
+                buffer.push_str("<!--");
+                buffer.push_str(comment);
+                buffer.push_str("-->");
+            }
+            Node::Text(text) => buffer.push_str(text),
+            Node::Element(element) => {
+                element.render_open_tag(buffer);
+                if !element.is_void() && !element.is_empty_tag() {
+                    element
+                        .children
+                        .iter()
+                        .for_each(|child| child.render_to_string(buffer));
+                    element.render_close_tag(buffer);
+                }
+            }
+        }
+    }
+
+    fn render_nodes(&self, nodes: &mut Vec<Node>) {
+        nodes.push(self.clone());
+    }
+}
+
+impl Component {
+    /// Parses the rendered html into a tree of nodes, the same as
+    /// `parse(&component.html)`.
+    pub fn nodes(&self) -> Vec<Node> {
+        parse(&self.html)
+    }
+}
+
+impl From<Vec<Node>> for Component {
+    fn from(nodes: Vec<Node>) -> Self {
+        let mut html = String::new();
+        nodes.render_to_string(&mut html);
+        Component { html }
+    }
+}
+
+/// Parses html into nodes. Text and attribute values are kept exactly as
+/// they appear in the html, entities and all, and so is `/>` on tags, so
+/// rendering the nodes again gives back the same html.
+///
+/// This is a forgiving parser for html you already trust the shape of, like
+/// the output of `html!`. It doesn't follow the html5 tree building rules for
+/// misnested or implicitly closed tags.
+pub fn parse(html: &str) -> Vec<Node> {
+    let mut parser = Parser {
+        html,
+        pos: 0,
+        stack: Vec::new(),
+        nodes: Vec::new(),
+    };
+    parser.parse();
+    parser.nodes
+}
+
+struct Parser<'a> {
+    html: &'a str,
+    pos: usize,
+    stack: Vec<Element>,
+    nodes: Vec<Node>,
+}
+
+impl Parser<'_> {
+    fn parse(&mut self) {
+        while self.pos < self.html.len() {
+            let rest = &self.html[self.pos..];
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment.find("-->").unwrap_or(comment.len());
+                self.push(Node::Comment(comment[..end].to_owned()));
+                self.pos += 4 + (end + 3).min(comment.len());
+            } else if let Some(doctype) = rest.strip_prefix("<!") {
+                let end = doctype.find('>').unwrap_or(doctype.len());
+                self.push(Node::Doctype(doctype[..end].to_owned()));
+                self.pos += 2 + (end + 1).min(doctype.len());
+            } else if rest.starts_with("</") && starts_with_letter(&rest[2..]) {
+                let end = rest.find('>').unwrap_or(rest.len());
+                let name = rest[2..end].trim();
+                self.pos += (end + 1).min(rest.len());
+                self.close(name);
+            } else if rest.starts_with('<') && starts_with_letter(&rest[1..]) {
+                self.pos += 1;
+                self.open_tag();
+            } else {
+                let first = rest.chars().next().map_or(1, char::len_utf8);
+                let end = rest[first..]
+                    .find('<')
+                    .map(|end| end + first)
+                    .unwrap_or(rest.len());
+                self.push(Node::Text(rest[..end].to_owned()));
+                self.pos += end;
+            }
+        }
+
+        while let Some(element) = self.stack.pop() {
+            self.push(Node::Element(element));
+        }
+    }
+
+    fn push(&mut self, node: Node) {
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.nodes.push(node),
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        if let Some(i) = self
+            .stack
+            .iter()
+            .rposition(|element| element.name.eq_ignore_ascii_case(name))
+        {
+            while self.stack.len() > i {
+                let element = self.stack.pop().unwrap();
+                self.push(Node::Element(element));
+            }
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &str {
+        let rest = &self.html[self.pos..];
+        let end = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn open_tag(&mut self) {
+        let name = self
+            .take_while(|c| !c.is_whitespace() && c != '/' && c != '>')
+            .to_owned();
+        let mut element = Element::new(name);
+
+        loop {
+            self.take_while(char::is_whitespace);
+            let rest = &self.html[self.pos..];
+            if rest.is_empty() {
+                break;
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            } else if rest.starts_with("/>") {
+                self.pos += 2;
+                element.self_closing = true;
+                break;
+            } else if rest.starts_with('/') {
+                self.pos += 1;
+                continue;
+            }
+
+            let key = self
+                .take_while(|c| !c.is_whitespace() && c != '=' && c != '>' && c != '/')
+                .to_owned();
+            self.take_while(char::is_whitespace);
+            let value = match self.html[self.pos..].starts_with('=') {
+                true => {
+                    self.pos += 1;
+                    self.take_while(char::is_whitespace);
+                    Some(self.attr_value())
+                }
+                false => None,
+            };
+            element.attrs.push((key, value));
+        }
+
+        let raw_text = RAW_TEXT_ELEMENTS
+            .iter()
+            .any(|name| element.name.eq_ignore_ascii_case(name));
+        if element.self_closing || element.is_void() {
+            self.push(Node::Element(element));
+        } else if raw_text {
+            let rest = &self.html[self.pos..];
+            let end = find_close_tag(rest, &element.name).unwrap_or(rest.len());
+            if end > 0 {
+                element.children.push(Node::Text(rest[..end].to_owned()));
+            }
+            self.pos += end;
+            let rest = &self.html[self.pos..];
+            self.pos += rest.find('>').map(|end| end + 1).unwrap_or(rest.len());
+            self.push(Node::Element(element));
+        } else {
+            self.stack.push(element);
+        }
+    }
+
+    fn attr_value(&mut self) -> String {
+        let rest = &self.html[self.pos..];
+        match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..].find(quote).unwrap_or(rest.len() - 1);
+                self.pos += (end + 2).min(rest.len());
+                rest[1..1 + end].to_owned()
+            }
+            _ => self
+                .take_while(|c| !c.is_whitespace() && c != '>')
+                .to_owned(),
+        }
+    }
+}
+
+fn starts_with_letter(html: &str) -> bool {
+    html.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+fn find_close_tag(html: &str, name: &str) -> Option<usize> {
+    let mut start = 0;
+    while let Some(i) = html[start..].find("</") {
+        let i = start + i;
+        let candidate = html[i + 2..].get(..name.len());
+        if candidate.is_some_and(|candidate| candidate.eq_ignore_ascii_case(name)) {
+            return Some(i);
+        }
+        start = i + 2;
+    }
+    None
+}
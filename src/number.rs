@@ -0,0 +1,294 @@
+use crate::{escape_into, Render};
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+const COMPACT_UNITS: [&str; 4] = ["k", "M", "B", "T"];
+
+/// A number formatted for people to read, like `1,234.50`, `12.5%`, `-$5.00`
+/// or `15.3k`, written straight into the buffer without a `format!`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Number<'a> {
+    value: Value,
+    decimals: Option<usize>,
+    separator: Option<char>,
+    point: char,
+    prefix: &'a str,
+    suffix: &'a str,
+    compact: bool,
+}
+
+/// Integers are kept as integers so large ones like `u64::MAX` print every
+/// digit instead of going through `f64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Signed(i128),
+    Unsigned(u128),
+    Float(f64),
+}
+
+impl Value {
+    fn as_f64(self) -> f64 {
+        match self {
+            Value::Signed(value) => value as f64,
+            Value::Unsigned(value) => value as f64,
+            Value::Float(value) => value,
+        }
+    }
+}
+
+impl<'a> Number<'a> {
+    pub fn new(value: f64) -> Self {
+        Self::with_value(Value::Float(value))
+    }
+
+    fn with_value(value: Value) -> Self {
+        Self {
+            value,
+            decimals: None,
+            separator: None,
+            point: '.',
+            prefix: "",
+            suffix: "",
+            compact: false,
+        }
+    }
+
+    /// Always shows this many decimal places, rounding the rest.
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// Groups the digits before the decimal point in threes.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// The decimal point, for locales that use a comma.
+    pub fn point(mut self, point: char) -> Self {
+        self.point = point;
+        self
+    }
+
+    /// Shows a fraction like `0.25` as `25%`.
+    pub fn percent(mut self) -> Self {
+        self.value = match self.value {
+            Value::Signed(value) => value.checked_mul(100).map(Value::Signed),
+            Value::Unsigned(value) => value.checked_mul(100).map(Value::Unsigned),
+            Value::Float(_) => None,
+        }
+        .unwrap_or(Value::Float(self.value.as_f64() * 100.0));
+        self.suffix = "%";
+        self
+    }
+
+    /// Puts `symbol` before the number, with two decimal places unless
+    /// `decimals` says otherwise.
+    pub fn currency(mut self, symbol: &'a str) -> Self {
+        self.prefix = symbol;
+        self.decimals = self.decimals.or(Some(2));
+        self
+    }
+
+    /// Shows thousands, millions, billions and trillions as `k`, `M`, `B` and
+    /// `T` with up to one decimal place unless `decimals` says otherwise.
+    pub fn compact(mut self) -> Self {
+        self.compact = true;
+        self
+    }
+}
+
+macro_rules! impl_from_number {
+    ($variant:ident as $as:ty: $($t:ty),*) => {
+        $(
+            impl From<$t> for Number<'_> {
+                fn from(value: $t) -> Self {
+                    Number::with_value(Value::$variant(value as $as))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_number!(Signed as i128: i8, i16, i32, i64, i128, isize);
+impl_from_number!(Unsigned as u128: u8, u16, u32, u64, u128, usize);
+impl_from_number!(Float as f64: f32, f64);
+
+impl Render for Number<'_> {
+    fn render_to_string(&self, buffer: &mut String) {
+        let integer = match self.value {
+            _ if self.compact => None,
+            Value::Signed(value) => Some((value < 0, value.unsigned_abs())),
+            Value::Unsigned(value) => Some((false, value)),
+            Value::Float(_) => None,
+        };
+        if let Some((negative, value)) = integer {
+            return self.render_integer(negative, value, buffer);
+        }
+
+        let float = self.value.as_f64();
+        if !float.is_finite() {
+            let _ = write!(buffer, "{}", float);
+            return;
+        }
+
+        let mut value = float.abs();
+        let mut unit = "";
+        let decimals = match (self.decimals, self.compact) {
+            (Some(decimals), _) => Some(decimals),
+            (None, true) => Some(1),
+            (None, false) => None,
+        };
+        if self.compact {
+            for next in COMPACT_UNITS {
+                let mut digits = Digits::default();
+                let _ = write_digits(&mut digits, value, decimals);
+                if digits.integer < 4 {
+                    break;
+                }
+                value /= 1000.0;
+                unit = next;
+            }
+        }
+
+        let mut digits = Digits::default();
+        let _ = write_digits(&mut digits, value, decimals);
+        let trim = self.compact && self.decimals.is_none();
+        let keep = match trim {
+            true => digits.significant.max(digits.integer),
+            false => digits.len,
+        };
+
+        if float < 0.0 && digits.nonzero {
+            buffer.push('-');
+        }
+        let _ = escape_into(self.prefix, buffer);
+        let _ = write_digits(
+            &mut Grouped {
+                buffer,
+                integer: digits.integer,
+                keep,
+                separator: self.separator,
+                point: self.point,
+            },
+            value,
+            decimals,
+        );
+        buffer.push_str(unit);
+        let _ = escape_into(self.suffix, buffer);
+    }
+
+    fn size_hint(&self) -> usize {
+        24 + self.prefix.len() + self.suffix.len()
+    }
+}
+
+impl Number<'_> {
+    /// Writes an integer's digits with itoa, padding any decimal places with
+    /// zeros.
+    fn render_integer(&self, negative: bool, value: u128, buffer: &mut String) {
+        let mut itoa = itoa::Buffer::new();
+        let digits = itoa.format(value);
+        let decimals = self.decimals.unwrap_or(0);
+
+        if negative {
+            buffer.push('-');
+        }
+        let _ = escape_into(self.prefix, buffer);
+        let mut grouped = Grouped {
+            buffer,
+            integer: digits.len(),
+            keep: usize::MAX,
+            separator: self.separator,
+            point: self.point,
+        };
+        let _ = grouped.write_str(digits);
+        if decimals > 0 {
+            let _ = grouped.write_str(".");
+            (0..decimals).for_each(|_| grouped.buffer.push('0'));
+        }
+        let _ = escape_into(self.suffix, buffer);
+    }
+}
+
+fn write_digits(writer: &mut impl Write, value: f64, decimals: Option<usize>) -> fmt::Result {
+    match decimals {
+        Some(decimals) => write!(writer, "{:.*}", decimals, value),
+        None => write!(writer, "{}", value),
+    }
+}
+
+/// Measures the formatted number so it can be grouped and trimmed when it's
+/// written out.
+#[derive(Default)]
+struct Digits {
+    len: usize,
+    integer: usize,
+    significant: usize,
+    point: bool,
+    nonzero: bool,
+}
+
+impl Write for Digits {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.len += 1;
+            match c {
+                '.' => self.point = true,
+                '0' => {}
+                _ => {
+                    self.nonzero = true;
+                    if self.point {
+                        self.significant = self.len;
+                    }
+                }
+            }
+            if !self.point {
+                self.integer += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the first `keep` characters of the number, with a separator
+/// between groups of three integer digits.
+struct Grouped<'a> {
+    buffer: &'a mut String,
+    integer: usize,
+    keep: usize,
+    separator: Option<char>,
+    point: char,
+}
+
+impl Write for Grouped<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.keep == 0 {
+                break;
+            }
+            self.keep -= 1;
+            if c == '.' {
+                self.push(self.point)?;
+            } else if self.integer > 0 {
+                self.buffer.push(c);
+                self.integer -= 1;
+                if let Some(separator) = self.separator {
+                    if self.integer > 0 && self.integer.is_multiple_of(3) {
+                        self.push(separator)?;
+                    }
+                }
+            } else {
+                self.buffer.push(c);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Grouped<'_> {
+    fn push(&mut self, c: char) -> fmt::Result {
+        escape_into(c.encode_utf8(&mut [0; 4]), self.buffer)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    node::{parse, Element, Node},
+    Component, Render,
+};
+use alloc::string::String;
+
+const INDENT: &str = "  ";
+
+const INLINE_ELEMENTS: [&str; 30] = [
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "dfn", "em", "i",
+    "img", "input", "kbd", "label", "mark", "q", "s", "samp", "select", "small", "span", "strong",
+    "sub", "sup", "time", "u", "var",
+];
+
+/// Elements whose content is printed exactly as rendered.
+const PREFORMATTED_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+impl Component {
+    /// Indents the html and puts block elements on their own lines, for
+    /// readable snapshots and view source while developing. Inline elements
+    /// stay on the line with their text and `<pre>`, `<textarea>`,
+    /// `<script>` and `<style>` are left untouched.
+    pub fn pretty(&self) -> Component {
+        let mut html = String::new();
+        block(&parse(&self.html), 0, &mut html);
+        let len = html.trim_end_matches('\n').len();
+        html.truncate(len);
+        Component { html }
+    }
+}
+
+fn is_named(element: &Element, names: &[&str]) -> bool {
+    names
+        .iter()
+        .any(|name| element.name.eq_ignore_ascii_case(name))
+}
+
+fn is_inline(node: &Node) -> bool {
+    match node {
+        Node::Text(_) => true,
+        Node::Element(element) => {
+            is_named(element, &INLINE_ELEMENTS) && element.children.iter().all(is_inline)
+        }
+        Node::Doctype(_) | Node::Comment(_) => false,
+    }
+}
+
+fn line(html: &mut String, depth: usize, content: &str) {
+    (0..depth).for_each(|_| html.push_str(INDENT));
+    html.push_str(content);
+    html.push('\n');
+}
+
+fn block(nodes: &[Node], depth: usize, html: &mut String) {
+    let mut inline = String::new();
+    for node in nodes {
+        match is_inline(node) {
+            true => node.render_to_string(&mut inline),
+            false => {
+                flush(&mut inline, depth, html);
+                let mut content = String::new();
+                match node {
+                    Node::Element(element)
+                        if !is_named(element, &PREFORMATTED_ELEMENTS)
+                            && !element.children.iter().all(is_inline) =>
+                    {
+                        element.render_open_tag(&mut content);
+                        line(html, depth, &content);
+                        block(&element.children, depth + 1, html);
+                        content.clear();
+                        element.render_close_tag(&mut content);
+                        line(html, depth, &content);
+                    }
+                    _ => {
+                        node.render_to_string(&mut content);
+                        line(html, depth, &content);
+                    }
+                }
+            }
+        }
+    }
+    flush(&mut inline, depth, html);
+}
+
+fn flush(inline: &mut String, depth: usize, html: &mut String) {
+    let content = inline.trim();
+    if !content.is_empty() {
+        line(html, depth, content);
+    }
+    inline.clear();
+}
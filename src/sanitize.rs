@@ -0,0 +1,327 @@
+use crate::{parse, Component, Element, Node};
+use alloc::{string::String, vec::Vec};
+
+/// Tags that are removed along with everything in them when they aren't
+/// allowed, since their content isn't text people are meant to read.
+const DROPPED_TAGS: [&str; 18] = [
+    "applet",
+    "embed",
+    "frameset",
+    "iframe",
+    "math",
+    "noembed",
+    "noframes",
+    "noscript",
+    "object",
+    "plaintext",
+    "script",
+    "select",
+    "style",
+    "svg",
+    "template",
+    "textarea",
+    "title",
+    "xmp",
+];
+
+/// Attributes holding a url, which are only kept when the url is relative or
+/// uses an allowed scheme.
+const URL_ATTRS: [&str; 8] = [
+    "action",
+    "background",
+    "cite",
+    "formaction",
+    "href",
+    "poster",
+    "src",
+    "xlink:href",
+];
+
+const DEFAULT_TAGS: [&str; 45] = [
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+];
+
+/// Cleans untrusted html down to an allowlist of tags and attributes.
+///
+/// Tags that aren't allowed are unwrapped, keeping their text, except ones
+/// like `script` and `style` which are removed with their content. Comments
+/// are removed and text is re-escaped so the output parses the same way
+/// everywhere.
+#[derive(Debug, Clone)]
+pub struct Sanitizer<'a> {
+    tags: Vec<&'a str>,
+    attrs: Vec<(&'a str, &'a str)>,
+    url_schemes: Vec<&'a str>,
+    link_rel: Option<&'a str>,
+}
+
+impl Default for Sanitizer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Sanitizer<'a> {
+    /// A policy for rich text: formatting, headings, lists, tables, links and
+    /// images, with `http`, `https` and `mailto` urls.
+    pub fn new() -> Self {
+        Self::empty()
+            .allow_tags(&DEFAULT_TAGS)
+            .allow_attrs("*", &["title", "lang"])
+            .allow_attrs("a", &["href"])
+            .allow_attrs("img", &["src", "alt", "width", "height"])
+            .allow_attrs("blockquote", &["cite"])
+            .allow_attrs("q", &["cite"])
+            .allow_attrs("ol", &["start"])
+            .allow_attrs("td", &["colspan", "rowspan"])
+            .allow_attrs("th", &["colspan", "rowspan"])
+            .url_schemes(&["http", "https", "mailto"])
+            .link_rel(Some("noopener noreferrer"))
+    }
+
+    /// A policy that allows nothing, leaving only text.
+    pub fn empty() -> Self {
+        Self {
+            tags: Vec::new(),
+            attrs: Vec::new(),
+            url_schemes: Vec::new(),
+            link_rel: None,
+        }
+    }
+
+    pub fn allow_tags(mut self, tags: &[&'a str]) -> Self {
+        self.tags.extend(tags);
+        self
+    }
+
+    pub fn deny_tags(mut self, tags: &[&str]) -> Self {
+        self.tags
+            .retain(|tag| !tags.iter().any(|denied| denied.eq_ignore_ascii_case(tag)));
+        self
+    }
+
+    /// Allows `attrs` on `tag`, or on every allowed tag when `tag` is `*`.
+    pub fn allow_attrs(mut self, tag: &'a str, attrs: &[&'a str]) -> Self {
+        self.attrs.extend(attrs.iter().map(|attr| (tag, *attr)));
+        self
+    }
+
+    /// The schemes allowed in urls, replacing the current ones. Relative urls
+    /// are always allowed.
+    pub fn url_schemes(mut self, schemes: &[&'a str]) -> Self {
+        self.url_schemes = schemes.to_vec();
+        self
+    }
+
+    /// The `rel` set on every link, so pages opened from user content can't
+    /// reach back into this one.
+    pub fn link_rel(mut self, rel: Option<&'a str>) -> Self {
+        self.link_rel = rel;
+        self
+    }
+
+    pub fn sanitize(&self, html: &str) -> Component {
+        let mut nodes = Vec::new();
+        self.clean(parse(html), &mut nodes);
+        Component::from(nodes)
+    }
+
+    fn clean(&self, nodes: Vec<Node>, output: &mut Vec<Node>) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push(Node::Text(escape_text(&text, false))),
+                Node::Element(element) if self.allows_tag(&element.name) => {
+                    output.push(Node::Element(self.clean_element(element)));
+                }
+                Node::Element(element) if !is_dropped(&element.name) => {
+                    self.clean(element.children, output);
+                }
+                Node::Element(_) | Node::Comment(_) | Node::Doctype(_) => {}
+            }
+        }
+    }
+
+    fn clean_element(&self, element: Element) -> Element {
+        let mut clean = Element::new(element.name.to_ascii_lowercase());
+        for (key, value) in element.attrs {
+            let key = key.to_ascii_lowercase();
+            if !self.allows_attr(&clean.name, &key)
+                || clean.attrs.iter().any(|(existing, _)| *existing == key)
+            {
+                continue;
+            }
+            let value = value.map(|value| escape_text(&value, true));
+            let is_url = URL_ATTRS.contains(&key.as_str());
+            match value {
+                Some(value) if is_url && !self.allows_url(&value) => {}
+                None if is_url => {}
+                value => clean.attrs.push((key, value)),
+            }
+        }
+        if let (Some(rel), "a") = (self.link_rel, clean.name.as_str()) {
+            clean.set_attr("rel", rel);
+        }
+        if !clean.is_void() {
+            self.clean(element.children, &mut clean.children);
+        }
+
+        clean
+    }
+
+    fn allows_tag(&self, name: &str) -> bool {
+        self.tags.iter().any(|tag| tag.eq_ignore_ascii_case(name))
+    }
+
+    fn allows_attr(&self, tag: &str, name: &str) -> bool {
+        self.attrs.iter().any(|(allowed_tag, attr)| {
+            (*allowed_tag == "*" || allowed_tag.eq_ignore_ascii_case(tag))
+                && attr.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Whether a url, escaped as it was in the html, is relative or uses an
+    /// allowed scheme. Named entities before the path are rejected since
+    /// browsers decode ones like `&colon;` into the scheme.
+    fn allows_url(&self, url: &str) -> bool {
+        let url = decode_numeric(url)
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+            .collect::<String>();
+        let head = url.split(['/', '?', '#']).next().unwrap_or("");
+        if head.contains('&') {
+            return false;
+        }
+
+        match head.split_once(':') {
+            Some((scheme, _)) => self
+                .url_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme)),
+            None => true,
+        }
+    }
+}
+
+fn is_dropped(name: &str) -> bool {
+    DROPPED_TAGS
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case(name))
+}
+
+/// Escapes the `<`, `>` and stray `&` left in text from the parser, and quotes
+/// in attribute values, keeping entities that are already there.
+fn escape_text(text: &str, attr: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' if !is_entity(&text[i + 1..]) => escaped.push_str("&amp;"),
+            '"' if attr => escaped.push_str("&quot;"),
+            '\'' if attr => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Whether the text after a `&` is a complete entity like `amp;`, `#39;` or
+/// `#x27;`.
+fn is_entity(rest: &str) -> bool {
+    let Some(end) = rest.find(';') else {
+        return false;
+    };
+    let name = &rest[..end];
+    match name.strip_prefix('#') {
+        Some(hex) if hex.starts_with(['x', 'X']) => {
+            hex.len() > 1 && hex[1..].chars().all(|c| c.is_ascii_hexdigit())
+        }
+        Some(digits) => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+        None => !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()),
+    }
+}
+
+/// Decodes `&#106;` and `&#x6A;` references, with or without the `;` like
+/// browsers do, leaving named entities alone.
+fn decode_numeric(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("&#") {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 2..];
+        let hex = rest.starts_with(['x', 'X']);
+        let digits = match hex {
+            true => &rest[1..],
+            false => rest,
+        };
+        let len = digits
+            .find(|c: char| {
+                !(if hex {
+                    c.is_ascii_hexdigit()
+                } else {
+                    c.is_ascii_digit()
+                })
+            })
+            .unwrap_or(digits.len());
+        if len == 0 {
+            decoded.push_str("&#");
+            continue;
+        }
+        let code = u32::from_str_radix(&digits[..len], if hex { 16 } else { 10 }).ok();
+        decoded.push(
+            code.and_then(char::from_u32)
+                .filter(|c| *c != '\0')
+                .unwrap_or('\u{fffd}'),
+        );
+        rest = &digits[len..];
+        rest = rest.strip_prefix(';').unwrap_or(rest);
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
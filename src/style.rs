@@ -0,0 +1,78 @@
+use crate::Render;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// Builds a `Style` from `"property" => value` pairs.
+#[macro_export]
+macro_rules! style {
+    ($($property:expr => $value:expr),* $(,)?) => {
+        $crate::Style::new()$(.with($property, $value))*
+    };
+}
+
+/// Inline css declarations rendered as `property: value;`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Style<'a> {
+    declarations: Vec<(Cow<'a, str>, String)>,
+}
+
+impl<'a> Style<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, property: impl Into<Cow<'a, str>>, value: impl fmt::Display) -> Self {
+        self.push(property, value);
+        self
+    }
+
+    pub fn push(&mut self, property: impl Into<Cow<'a, str>>, value: impl fmt::Display) {
+        let mut string = String::new();
+        let _ = write!(string, "{}", value);
+        self.declarations.push((property.into(), string));
+    }
+
+    pub fn merge(mut self, other: Style<'a>) -> Self {
+        self.declarations.extend(other.declarations);
+        self
+    }
+}
+
+impl Render for Style<'_> {
+    fn render_to_string(&self, buffer: &mut String) {
+        for (i, (property, value)) in self.declarations.iter().enumerate() {
+            if i > 0 {
+                buffer.push(' ');
+            }
+            buffer.push_str(&escape_css(property.as_ref()));
+            buffer.push_str(": ");
+            buffer.push_str(&escape_css(value.as_str()));
+            buffer.push(';');
+        }
+    }
+}
+
+/// Escapes anything that could end a css declaration or the surrounding
+/// html attribute using css hex escapes like `\3b `.
+pub fn escape_css<'a, S: Into<Cow<'a, str>>>(input: S) -> Cow<'a, str> {
+    let input = input.into();
+    fn needs_escaping(c: char) -> bool {
+        c.is_ascii() && !c.is_ascii_alphanumeric() && !" -_.,%#()/+!:*".contains(c)
+    }
+
+    if let Some(first) = input.find(needs_escaping) {
+        let mut output = String::from(&input[0..first]);
+        output.reserve(input.len() - first);
+        for c in input[first..].chars() {
+            match needs_escaping(c) {
+                true => {
+                    let _ = write!(output, "\\{:x} ", c as u32);
+                }
+                false => output.push(c),
+            }
+        }
+        Cow::Owned(output)
+    } else {
+        input
+    }
+}
@@ -0,0 +1,400 @@
+use crate::{
+    node::{parse, Element, Node},
+    Component,
+};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+/// Asserts two pieces of html are the same, ignoring attribute order and
+/// whitespace in text. Takes anything that implements `Display`, like
+/// `Component` or `&str`, and shows both sides pretty printed when they differ.
+#[macro_export]
+macro_rules! assert_html_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_html_eq(&$left, &$right)
+    };
+}
+
+#[track_caller]
+pub fn assert_html_eq(left: &impl fmt::Display, right: &impl fmt::Display) {
+    let left = normalize(&left.to_string());
+    let right = normalize(&right.to_string());
+    assert!(
+        left == right,
+        "html is not equal\n\nleft:\n{}\n\nright:\n{}\n",
+        left,
+        right
+    );
+}
+
+#[cfg(feature = "insta")]
+#[doc(hidden)]
+pub use insta as __insta;
+
+/// Snapshots a component with insta after normalizing and pretty printing it,
+/// so the snapshot file reads like html and diffs line by line. Takes an
+/// optional snapshot name first, like `insta::assert_snapshot!`.
+#[cfg(feature = "insta")]
+#[macro_export]
+macro_rules! assert_html_snapshot {
+    ($value:expr $(,)?) => {
+        $crate::assert_html_snapshot!(None::<&str>, $value)
+    };
+    ($name:expr, $value:expr $(,)?) => {
+        $crate::testing::__insta::assert_snapshot!(
+            $name,
+            $crate::testing::snapshot(&$value),
+            stringify!($value)
+        )
+    };
+}
+
+/// The text `assert_html_snapshot!` stores, for use with other snapshot tools.
+pub fn snapshot(html: &impl fmt::Display) -> String {
+    normalize(&html.to_string())
+}
+
+/// Parses and pretty prints html with attributes sorted, whitespace in text
+/// collapsed and entities written the same way, so html that means the same
+/// thing normalizes to the same string.
+pub fn normalize(html: &str) -> String {
+    let nodes = parse(html)
+        .into_iter()
+        .filter_map(|node| normalize_node(node, false))
+        .collect::<Vec<_>>();
+    Component::from(nodes).pretty().html
+}
+
+fn normalize_node(node: Node, preformatted: bool) -> Option<Node> {
+    match node {
+        Node::Text(text) if preformatted => Some(Node::text(&unescape(&text))),
+        Node::Text(text) => {
+            let text = unescape(&text);
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            match text.is_empty() {
+                true => None,
+                false => Some(Node::text(&text)),
+            }
+        }
+        Node::Element(mut element) => {
+            let preformatted = preformatted
+                || ["pre", "textarea", "script", "style"]
+                    .iter()
+                    .any(|name| element.name.eq_ignore_ascii_case(name));
+            element.name.make_ascii_lowercase();
+            for (key, value) in &mut element.attrs {
+                if let Some(value) = value {
+                    *value = crate::escape(unescape(value)).into_owned();
+                }
+                key.make_ascii_lowercase();
+            }
+            element.attrs.sort();
+            element.children = element
+                .children
+                .into_iter()
+                .filter_map(|child| normalize_node(child, preformatted))
+                .collect();
+            Some(Node::Element(element))
+        }
+        node => Some(node),
+    }
+}
+
+/// Decodes the entities `escape` produces along with `&apos;`, `&nbsp;` and
+/// numeric character references.
+pub fn unescape(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(i) = rest.find('&') {
+        output.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let number = entity.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                output.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+impl Component {
+    /// Returns every element matching a css selector.
+    ///
+    /// Supports type, `#id`, `.class`, `*` and `[attr]`, `[attr=value]`,
+    /// `[attr~=value]`, `[attr^=value]`, `[attr$=value]` and `[attr*=value]`
+    /// selectors, the descendant and `>` child combinators, and lists of
+    /// selectors separated by commas. Panics on anything else.
+    #[track_caller]
+    pub fn select(&self, selector: &str) -> Vec<Element> {
+        select(&self.nodes(), selector)
+    }
+
+    /// The decoded text of the whole component.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.nodes()
+            .iter()
+            .for_each(|node| push_text(node, &mut text));
+        text
+    }
+}
+
+impl Element {
+    /// Returns every element below this one matching a css selector.
+    #[track_caller]
+    pub fn select(&self, selector: &str) -> Vec<Element> {
+        select(&self.children, selector)
+    }
+
+    /// The decoded text of the element and everything in it.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.children
+            .iter()
+            .for_each(|node| push_text(node, &mut text));
+        text
+    }
+
+    /// The decoded value of an attribute.
+    pub fn attr_value(&self, key: &str) -> Option<String> {
+        self.attr(key).map(unescape)
+    }
+}
+
+fn push_text(node: &Node, text: &mut String) {
+    match node {
+        Node::Text(value) => text.push_str(&unescape(value)),
+        Node::Element(element) => element
+            .children
+            .iter()
+            .for_each(|child| push_text(child, text)),
+        Node::Doctype(_) | Node::Comment(_) => {}
+    }
+}
+
+#[track_caller]
+fn select(nodes: &[Node], selector: &str) -> Vec<Element> {
+    let selectors = selector
+        .split(',')
+        .map(|selector| match Selector::parse(selector) {
+            Ok(selector) => selector,
+            Err(error) => panic!("invalid selector {:?}: {}", selector, error),
+        })
+        .collect::<Vec<_>>();
+    let mut elements = Vec::new();
+    let mut ancestors = Vec::new();
+    collect(nodes, &selectors, &mut ancestors, &mut elements);
+    elements
+}
+
+fn collect<'a>(
+    nodes: &'a [Node],
+    selectors: &[Selector],
+    ancestors: &mut Vec<&'a Element>,
+    elements: &mut Vec<Element>,
+) {
+    for element in nodes.iter().filter_map(Node::as_element) {
+        if selectors
+            .iter()
+            .any(|selector| selector.matches(&selector.parts, element, ancestors))
+        {
+            elements.push(element.clone());
+        }
+        ancestors.push(element);
+        collect(&element.children, selectors, ancestors, elements);
+        ancestors.pop();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Default)]
+struct Compound {
+    name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<(String, Option<(String, String)>)>,
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        let name = self
+            .name
+            .as_ref()
+            .is_none_or(|name| name == "*" || element.name.eq_ignore_ascii_case(name));
+        let id = self
+            .id
+            .as_ref()
+            .is_none_or(|id| element.attr_value("id").as_ref() == Some(id));
+        let class = element.attr_value("class").unwrap_or_default();
+        let classes = self
+            .classes
+            .iter()
+            .all(|c| class.split_whitespace().any(|class| class == c));
+        let attrs = self
+            .attrs
+            .iter()
+            .all(|(key, op)| match (element.attr_value(key), op) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(value), Some((op, expected))) => match op.as_str() {
+                    "=" => value == *expected,
+                    "~=" => value.split_whitespace().any(|v| v == expected),
+                    "^=" => value.starts_with(expected.as_str()),
+                    "$=" => value.ends_with(expected.as_str()),
+                    _ => value.contains(expected.as_str()),
+                },
+            });
+
+        name && id && classes && attrs
+    }
+}
+
+#[derive(Debug)]
+struct Selector {
+    parts: Vec<(Combinator, Compound)>,
+}
+
+impl Selector {
+    fn parse(selector: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        let mut chars = selector.trim().chars().peekable();
+        let ident = |chars: &mut core::iter::Peekable<core::str::Chars>| {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                match c.is_alphanumeric() || c == '-' || c == '_' || c == '*' {
+                    true => ident.push(c),
+                    false => break,
+                }
+                chars.next();
+            }
+            ident
+        };
+
+        while chars.peek().is_some() {
+            let mut compound = Compound::default();
+            loop {
+                match chars.peek() {
+                    Some('#') => {
+                        chars.next();
+                        compound.id = Some(ident(&mut chars));
+                    }
+                    Some('.') => {
+                        chars.next();
+                        compound.classes.push(ident(&mut chars));
+                    }
+                    Some('[') => {
+                        chars.next();
+                        let mut attr = String::new();
+                        for c in chars.by_ref() {
+                            match c {
+                                ']' => break,
+                                c => attr.push(c),
+                            }
+                        }
+                        compound.attrs.push(parse_attr(&attr)?);
+                    }
+                    Some(c) if c.is_alphanumeric() || *c == '*' => {
+                        compound.name = Some(ident(&mut chars));
+                    }
+                    Some(c) if c.is_whitespace() || *c == '>' => break,
+                    None => break,
+                    Some(c) => return Err(alloc::format!("unsupported character {:?}", c)),
+                }
+            }
+            parts.push((combinator, compound));
+
+            combinator = Combinator::Descendant;
+            while let Some(&c) = chars.peek() {
+                match c {
+                    '>' => combinator = Combinator::Child,
+                    c if c.is_whitespace() => {}
+                    _ => break,
+                }
+                chars.next();
+            }
+        }
+
+        match parts.is_empty() {
+            true => Err("empty selector".to_owned()),
+            false => Ok(Self { parts }),
+        }
+    }
+
+    fn matches(
+        &self,
+        parts: &[(Combinator, Compound)],
+        element: &Element,
+        ancestors: &[&Element],
+    ) -> bool {
+        let Some(((combinator, compound), rest)) = parts.split_last() else {
+            return true;
+        };
+        if !compound.matches(element) {
+            return false;
+        }
+        if rest.is_empty() {
+            return true;
+        }
+        match combinator {
+            Combinator::Child => ancestors
+                .split_last()
+                .is_some_and(|(parent, ancestors)| self.matches(rest, parent, ancestors)),
+            Combinator::Descendant => (0..ancestors.len())
+                .rev()
+                .any(|i| self.matches(rest, ancestors[i], &ancestors[..i])),
+        }
+    }
+}
+
+fn parse_attr(attr: &str) -> Result<(String, Option<(String, String)>), String> {
+    let attr = attr.trim();
+    let Some(eq) = attr.find('=') else {
+        return Ok((attr.to_string(), None));
+    };
+    let (key, op) = match attr[..eq].char_indices().last() {
+        Some((i, c @ ('~' | '^' | '$' | '*'))) => (&attr[..i], alloc::format!("{}=", c)),
+        _ => (&attr[..eq], "=".to_owned()),
+    };
+    let value = attr[eq + 1..]
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'');
+    match key.trim().is_empty() {
+        true => Err(alloc::format!("missing attribute name in [{}]", attr)),
+        false => Ok((key.trim().to_string(), Some((op, value.to_string())))),
+    }
+}
@@ -0,0 +1,22 @@
+use crate::Render;
+use alloc::string::String;
+
+/// Renders `value` for `xml!`, dropping the control characters xml doesn't
+/// allow even when they're escaped.
+#[doc(hidden)]
+pub fn render<T: Render + ?Sized>(value: &T, buffer: &mut String) {
+    let start = buffer.len();
+    value.render_to_string(buffer);
+    if buffer[start..].contains(|c| !is_allowed(c)) {
+        let allowed = buffer[start..]
+            .chars()
+            .filter(|c| is_allowed(*c))
+            .collect::<String>();
+        buffer.truncate(start);
+        buffer.push_str(&allowed);
+    }
+}
+
+fn is_allowed(c: char) -> bool {
+    !matches!(c, '\0'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}')
+}